@@ -1,4 +1,4 @@
-# /germinate/templates wll be created at this path
+# the germinate binary will be copied to this path
 GERMINATE_PATH=~/clitools/germinate
 
 # use debug for development, release for production
@@ -14,9 +14,6 @@ fi
 
 echo "Building germinate in $BUILD mode" 
 
-echo "Removing old files from $GERMINATE_PATH"
-rm -rf ${GERMINATE_PATH}/templates 
-
-echo "Copying new files to $GERMINATE_PATH"	
+# the stock templates are built into the binary, run `germinate templates export <dir>` to customize them
+echo "Copying germinate to $GERMINATE_PATH"
 cp -p target/debug/germinate ${GERMINATE_PATH}
-cp -rf templates ${GERMINATE_PATH}
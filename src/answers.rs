@@ -0,0 +1,100 @@
+use std::{fs, path::Path};
+
+use slug::slugify;
+
+use crate::{
+    dialogue::UserOptions,
+    error::{GerminateError, Result},
+};
+
+pub const ANSWERS_FILE: &str = "germinate-answers.toml";
+
+/// Loads a `UserOptions` from a TOML or JSON answers file, picking the format by extension
+pub fn load(path: &Path) -> Result<UserOptions> {
+    let source = format!("answers file {}", path.display());
+    let contents = fs::read_to_string(path)
+        .map_err(GerminateError::io(format!("Failed to read {}", source)))?;
+    let mut options: UserOptions = match path.extension().and_then(|ext| ext.to_str()) {
+        Some("json") => {
+            serde_json::from_str(&contents).map_err(|e| GerminateError::input(&source, e))?
+        }
+        _ => toml::from_str(&contents).map_err(|e| GerminateError::input(&source, e))?,
+    };
+    options.output_dir = Path::new(&slugify(&options.app_name)).to_path_buf();
+    Ok(options)
+}
+
+pub fn to_string(options: &UserOptions) -> String {
+    let answers = toml::to_string_pretty(options).expect("Failed to serialize answers");
+    format!(
+        "# Replay this scaffold with `germinate new --answers {}`\n{}",
+        ANSWERS_FILE, answers
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{config::TemplateEngine, dialogue::SpaFramework, package_manager::PackageManager};
+
+    #[test]
+    fn answers_round_trip() {
+        let options = UserOptions {
+            stack: "rsapi".to_string(),
+            output_dir: Path::new("my-svc").to_path_buf(),
+            app_name: "My Svc".to_string(),
+            db: Some("postgres".to_string()),
+            db_client: None,
+            orm: true,
+            spa: Some(SpaFramework::React),
+            template_engine: Some(TemplateEngine::Tera),
+            containers: true,
+            package_manager: PackageManager::Pnpm,
+        };
+
+        let answers = to_string(&options);
+        assert!(answers.contains("stack = \"rsapi\""));
+        assert!(answers.contains("db = \"postgres\""));
+        assert!(answers.contains("spa = \"react\""));
+
+        let parsed: UserOptions = toml::from_str(&answers).expect("Failed to parse answers");
+        assert_eq!(parsed.stack, "rsapi");
+        assert_eq!(parsed.db.as_deref(), Some("postgres"));
+        assert_eq!(parsed.app_name, "My Svc");
+        assert!(parsed.orm && parsed.containers);
+        assert_eq!(parsed.template_engine, Some(TemplateEngine::Tera));
+        assert_eq!(parsed.package_manager, PackageManager::Pnpm);
+        assert_eq!(parsed.spa, Some(SpaFramework::React));
+
+        // answers saved before these choices recorded the SPA and template engine as booleans
+        let legacy = answers
+            .replace("spa = \"react\"", "spa = true")
+            .replace("template_engine = \"tera\"", "template_engine = true");
+        let parsed: UserOptions = toml::from_str(&legacy).expect("Failed to parse answers");
+        assert_eq!(parsed.spa, Some(SpaFramework::Vue));
+        assert_eq!(parsed.template_engine, Some(TemplateEngine::Handlebars));
+    }
+
+    #[test]
+    fn rejects_unknown_answers() {
+        let error = toml::from_str::<UserOptions>(
+            "stack = \"rsapi\"\napp_name = \"svc\"\ncontainer = true\n",
+        )
+        .unwrap_err();
+        assert!(
+            error.to_string().contains("unknown field `container`"),
+            "{}",
+            error
+        );
+    }
+
+    #[test]
+    fn load_json_answers() {
+        let path = Path::new("test/__mocks__/_answers.json");
+        let options = load(path).expect("Failed to load answers");
+
+        assert_eq!(options.stack, "tscli");
+        assert!(options.db.is_none());
+        assert_eq!(options.output_dir, Path::new("json-answers"));
+    }
+}
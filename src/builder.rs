@@ -1,200 +1,875 @@
 use crate::{
-    config::ScaffoldConfig, container::ContainerBuilder, dialogue::StackTemplate, file_system,
-    linters::Linter, module,
+    answers,
+    config::{Language, ScaffoldConfig},
+    container::ContainerBuilder,
+    error::{GerminateError, Result},
+    file_system,
+    journal::Journal,
+    linters::Linter,
+    module::{self, Module},
+    package_manager::PackageManager,
+    plan::{BuildReport, FailurePolicy, Plan, Step},
+    runner::CommandRunner,
+    staging::StagingDir,
+    template_generator::{self, ProjectVariables},
+};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    process::Command,
+    vec,
 };
-use std::{collections::HashMap, env, io::Result, path::PathBuf, process::Command, vec};
 
 pub struct ProjectBuilder {}
 
+#[derive(Debug, Clone, Copy)]
+pub struct BuildOptions {
+    pub policy: FailurePolicy,
+    /// Leave the staging directory behind when the build fails, for debugging
+    pub keep_on_failure: bool,
+}
+
 impl ProjectBuilder {
-    pub fn build(config: &ScaffoldConfig) {
+    /// Builds the project in a staging directory and renames it into place only if every step succeeded
+    pub fn build(
+        config: &ScaffoldConfig,
+        runner: &mut dyn CommandRunner,
+        options: BuildOptions,
+    ) -> Result<BuildReport> {
         println!("Building project...");
-        make_folders(&config.root_dir, &config.subfolders);
-        std::env::set_current_dir(&config.root_dir).expect("Failed to set current directory");
+        let staging = StagingDir::new(&config.root_dir, options.keep_on_failure)
+            .map_err(GerminateError::io("Failed to prepare staging directory"))?;
+        let plan = Self::plan(&config.with_root(staging.path()))?;
+        let journal = Journal::new(&config.user_options, staging.path(), &plan);
+        Self::finish(config, staging, plan, journal, runner, options)
+    }
+
+    /// Continues an interrupted build from the first step its journal hasn't marked done
+    pub fn resume(
+        config: &ScaffoldConfig,
+        runner: &mut dyn CommandRunner,
+        options: BuildOptions,
+    ) -> Result<BuildReport> {
+        println!("Resuming project build...");
+        let staging = StagingDir::existing(&config.root_dir, options.keep_on_failure)
+            .map_err(GerminateError::io("Failed to open staging directory"))?;
+        let plan = Self::plan(&config.with_root(staging.path()))?;
+        let journal = Journal::load(staging.path())?;
+        journal.verify(&plan)?;
+        Self::finish(config, staging, plan, journal, runner, options)
+    }
 
-        pre_install_commands(config).expect("Failed to run pre-install commands");
-        install_commands(config).expect("Failed to run install commands");
-        post_install_commands(config).expect("Failed to run post-install commands");
+    fn finish(
+        config: &ScaffoldConfig,
+        staging: StagingDir,
+        plan: Plan,
+        mut journal: Journal,
+        runner: &mut dyn CommandRunner,
+        options: BuildOptions,
+    ) -> Result<BuildReport> {
+        let report = plan.execute(runner, options.policy, &mut journal);
+        let failures = report.failures().count();
+        // `continue` asked for whatever could be built, so its failures are reported on the moved project
+        if failures == 0 || options.policy == FailurePolicy::Continue {
+            journal
+                .remove()
+                .map_err(GerminateError::io("Failed to remove build journal"))?;
+            staging
+                .commit(&config.root_dir)
+                .map_err(GerminateError::io(format!(
+                    "Failed to move project into {}",
+                    config.root_dir.display()
+                )))?;
+            let (_, cargo_deps) = install_modules(config);
+            let pinned = module::pin_cargo_paths(&config.root_dir, &cargo_deps);
+            let context = format!(
+                "Failed to point path dependencies at their crates in {}",
+                config.root_dir.display()
+            );
+            match pinned {
+                // a failed `cargo add` leaves nothing to point, and is already in the report
+                Err(e) if failures > 0 => eprintln!("{}: {}", context, e),
+                pinned => pinned.map_err(GerminateError::io(context))?,
+            }
+            if failures > 0 {
+                eprintln!(
+                    "Kept the project in {} despite {} failed step(s)",
+                    config.root_dir.display(),
+                    failures
+                );
+            }
+        }
+        Ok(report)
+    }
+
+    /// Collects every folder, file and command needed for the project without touching the disk
+    pub fn plan(config: &ScaffoldConfig) -> Result<Plan> {
+        let mut plan = Plan::new(&config.root_dir);
+        make_folders(&mut plan, &config.root_dir, &config.subfolders);
+        write_answers(&mut plan, config);
+        pre_install_commands(&mut plan, config)?;
+        install_commands(&mut plan, config)?;
+        post_install_commands(&mut plan, config)?;
+        Ok(plan)
     }
 }
 
-fn pre_install_commands(config: &ScaffoldConfig) -> Result<()> {
-    println!("Running pre-install commands...");
-    let pre_install_path = config.template_dir.join("before_install");
-    file_system::copy_dir_all(pre_install_path, env::current_dir().unwrap())
+fn write_answers(plan: &mut Plan, config: &ScaffoldConfig) {
+    println!("Queueing answers file...");
+    plan.push(Step::WriteFile {
+        path: config.root_dir.join(answers::ANSWERS_FILE),
+        contents: answers::to_string(&config.user_options),
+    });
 }
 
-fn install_commands(config: &ScaffoldConfig) -> Result<()> {
-    println!("Installing dependencies...");
-    for mut command in get_install_commands(config) {
-        println!("Running command: {:?}", command);
-        let output = command.output().expect("Failed to execute command");
-        println!("->> STDOUT: {}", String::from_utf8_lossy(&output.stdout));
-        println!("->> STDERR: {}", String::from_utf8_lossy(&output.stderr));
-    }
+fn pre_install_commands(plan: &mut Plan, config: &ScaffoldConfig) -> Result<()> {
+    println!("Queueing pre-install files...");
+    let pre_install_path = config.stack.dir.join("before_install");
+    copy_dir(plan, &pre_install_path, &config.root_dir)
+}
+
+fn install_commands(plan: &mut Plan, config: &ScaffoldConfig) -> Result<()> {
+    plan.run_all(get_install_commands(config)?);
     Ok(())
 }
 
-fn post_install_commands(config: &ScaffoldConfig) -> Result<()> {
-    println!("Running post-install commands...");
-
-    // stack specific commands
-    let stack = &config.user_options.stack;
-    match stack {
-        StackTemplate::RSAPI | StackTemplate::TSAPI => {
-            if config.user_options.template_engine {
-                println!("->> installing template engine");
-                match stack {
-                    StackTemplate::TSAPI => {
-                        let mut command = Command::new("bun");
-                        command.args(&["add", "handlebars"]);
-                        command.output().expect("Failed to execute command");
-                    }
-                    StackTemplate::RSAPI => {
-                        let mut command = Command::new("cargo");
-                        command.args(&["add", "handlebars"]);
-                        command.output().expect("Failed to execute command");
-                    }
-                    _ => (),
-                }
-            }
-            if config.user_options.spa {
-                println!("->> Creating Vue/Vite SPA");
-                let mut command = Command::new("bun");
-                command.args(&["create", "vue@latest"]);
-                command
-                    .spawn()
-                    .unwrap()
-                    .wait()
-                    .expect("Failed to execute command");
-            }
+fn post_install_commands(plan: &mut Plan, config: &ScaffoldConfig) -> Result<()> {
+    println!("Queueing post-install commands...");
+
+    // frontend commands
+    if config.stack.supports_frontend {
+        if let Some(spa) = config.user_options.spa {
+            // --overwrite clears the before_install placeholder files from frontend/
+            plan.run(config.user_options.package_manager.exec([
+                "create-vite@latest",
+                "frontend",
+                "--template",
+                spa.vite_template(),
+                "--overwrite",
+            ]));
         }
-        _ => (),
     }
 
     // general commands
-    println!("->> Removing boilerplate files...");
-    match stack {
-        StackTemplate::TSAPI | StackTemplate::TSCLI => {
-            std::fs::remove_file("index.ts").ok();
-        }
-        _ => {}
+    if config.has_language(&Language::TypeScript) {
+        plan.push(Step::RemoveFile(config.root_dir.join("index.ts")));
+    }
+    // views are templates themselves, so they're copied rather than rendered
+    if let Some(engine) = config.user_options.template_engine {
+        let views_path = config
+            .stack
+            .dir
+            .join("template_engine")
+            .join(engine.to_string());
+        copy_dir(plan, &views_path, &config.root_dir)?;
     }
-    println!("->> Copying Post-install templates...");
-    let post_install_path = config.template_dir.join("after_install");
-    file_system::copy_dir_all(post_install_path, env::current_dir().unwrap())
-        .expect("unable to copy dir");
+    let data = ProjectVariables::new(config);
+    render_after_install(plan, config, &data)?;
+    render_linter_configs(plan, config, &data)?;
 
     if config.containers {
-        ContainerBuilder::new(config).build();
+        for step in ContainerBuilder::new(config).plan()? {
+            plan.push(step);
+        }
     }
 
     if let Some(npm_scripts) = &config.npm_scripts {
-        set_npm_scripts(npm_scripts);
+        plan.run_all(set_npm_scripts(config, npm_scripts));
     }
 
-    create_repo();
+    plan.run_all(create_repo());
+
+    Ok(())
+}
+
+// after_install `.hbs` files are templates so the generated app can adapt to the chosen options
+fn render_after_install(
+    plan: &mut Plan,
+    config: &ScaffoldConfig,
+    data: &ProjectVariables,
+) -> Result<()> {
+    let post_install_path = config.stack.dir.join("after_install");
+    if !post_install_path.is_dir() {
+        return Ok(());
+    }
+    let steps = template_generator::plan_templates(&post_install_path, &config.root_dir, data)
+        .map_err(GerminateError::io(format!(
+            "Failed to read after_install templates in {}",
+            post_install_path.display()
+        )))?;
+    for step in steps {
+        plan.push(step);
+    }
+    Ok(())
+}
 
+fn render_linter_configs(
+    plan: &mut Plan,
+    config: &ScaffoldConfig,
+    data: &ProjectVariables,
+) -> Result<()> {
+    for linter in &config.linters {
+        if let Some(config_dir) = &linter.config_dir {
+            let steps = template_generator::plan_dir(config_dir, &config.root_dir, data).map_err(
+                GerminateError::io(format!(
+                    "Failed to read {} config templates in {}",
+                    linter.name,
+                    config_dir.display()
+                )),
+            )?;
+            for step in steps {
+                plan.push(step);
+            }
+        }
+    }
     Ok(())
 }
 
-fn make_folders(root_dir: &PathBuf, subfolders: &Option<Vec<PathBuf>>) {
-    println!("Making folders...");
+fn make_folders(plan: &mut Plan, root_dir: &Path, subfolders: &Option<Vec<PathBuf>>) {
+    println!("Queueing folders...");
+    plan.push(Step::CreateDir(root_dir.to_path_buf()));
     if let Some(folders) = subfolders {
         for folder in folders {
-            let full_path = root_dir.join(folder);
-            println!("Creating folder: {:?}", full_path);
-            std::fs::create_dir_all(&full_path)
-                .expect(format!("Failed to create folder: {:?}", &full_path).as_str());
+            plan.push(Step::CreateDir(root_dir.join(folder)));
         }
-    } else {
-        println!("Creating root folder only: {:?}", &root_dir);
-        std::fs::create_dir_all(root_dir).unwrap();
     }
 }
 
-fn get_install_commands(config: &ScaffoldConfig) -> Vec<Command> {
+// Stacks without a before/after install folder simply have nothing to copy
+fn copy_dir(plan: &mut Plan, src: &Path, dest: &Path) -> Result<()> {
+    if !src.is_dir() {
+        return Ok(());
+    }
+    let files = file_system::list_files(src).map_err(GerminateError::io(format!(
+        "Failed to list template files in {}",
+        src.display()
+    )))?;
+    for file in files {
+        plan.push(Step::CopyFile {
+            src: src.join(&file),
+            dest: dest.join(&file),
+        });
+    }
+    Ok(())
+}
+
+fn get_install_commands(config: &ScaffoldConfig) -> Result<Vec<Command>> {
     println!("Queueing install commands...");
     let mut commands = vec![];
 
-    commands.append(&mut generate_init_cmds(config));
+    commands.append(&mut generate_init_cmds(config)?);
 
-    if let Some(npm_deps) = &config.npm_deps {
-        commands.append(&mut module::get_npm_cmds(npm_deps));
-    }
-
-    if let Some(cargo_deps) = &config.cargo_deps {
-        commands.append(&mut module::get_cargo_cmds(cargo_deps));
-    }
+    let (npm_deps, cargo_deps) = install_modules(config);
+    let manager = config.user_options.package_manager;
+    commands.append(&mut module::get_npm_cmds(&npm_deps, manager));
+    commands.append(&mut module::get_cargo_cmds(
+        &cargo_deps,
+        manager,
+        &config.project_dir()?,
+    ));
 
     if let Some(db_client) = &config.db_client {
-        commands.append(&mut db_client.get_install_commands(config));
+        commands.append(&mut db_client.get_init_commands(manager));
     }
 
-    commands.append(&mut generate_linter_cmds(&config.linters));
+    commands.append(&mut generate_linter_cmds(&config.linters, manager));
 
-    commands
+    Ok(commands)
+}
+
+// the database client's modules join the stack's batches, so each package manager runs at most twice
+fn install_modules(config: &ScaffoldConfig) -> (Vec<Module>, Vec<Module>) {
+    let mut npm_deps = config.npm_deps.clone().unwrap_or_default();
+    let mut cargo_deps = config.cargo_deps.clone().unwrap_or_default();
+    if let Some(db_client) = &config.db_client {
+        match db_client.language {
+            Language::Rust => cargo_deps.extend(db_client.deps.iter().cloned()),
+            Language::TypeScript | Language::JavaScript => {
+                npm_deps.extend(db_client.deps.iter().cloned())
+            }
+        }
+    }
+    (npm_deps, cargo_deps)
 }
 
-fn generate_init_cmds(config: &ScaffoldConfig) -> Vec<Command> {
+// the staging folder is named like the project, but the name is passed explicitly wherever it can be
+fn generate_init_cmds(config: &ScaffoldConfig) -> Result<Vec<Command>> {
     let mut commands = vec![];
+    let package_name = config.package_name()?;
 
     if config.cargo_deps.is_some() {
         println!("Generating Cargo init...");
         let mut cargo_init = Command::new("cargo");
-        cargo_init.arg("init");
+        cargo_init.args(["init", "--name", package_name]);
         commands.push(cargo_init);
     }
     if config.npm_deps.is_some() {
         println!("Generating NPM init...");
-        let mut npm_init = Command::new("bun");
-        npm_init.args(&["init", "-y"]);
-        commands.push(npm_init);
-
-        let mut package_name = Command::new("npm");
-        package_name.args(&["pkg", "set", "name", &config.user_options.app_name]);
-        commands.push(package_name);
+        let manager = config.user_options.package_manager;
+        commands.push(manager.init());
+        commands.push(manager.pkg_set("name", package_name));
     }
 
-    commands
+    Ok(commands)
 }
 
-fn set_npm_scripts(scripts: &HashMap<String, String>) {
+fn set_npm_scripts(config: &ScaffoldConfig, scripts: &HashMap<String, String>) -> Vec<Command> {
     println!("Setting NPM scripts...");
-    for (name, script) in scripts {
-        let mut command = Command::new("npm");
-        command
-            .args(&["pkg", "set"])
-            .arg(format!("scripts.{}={}", name, script))
-            .output()
-            .expect("Failed to set npm scripts");
-    }
+    let manager = config.user_options.package_manager;
+    let mut names = scripts.keys().collect::<Vec<_>>();
+    names.sort();
+    names
+        .into_iter()
+        .map(|name| {
+            manager.pkg_set(
+                &format!("scripts.{}", name),
+                &manager.script(&scripts[name]),
+            )
+        })
+        .collect()
 }
 
-fn generate_linter_cmds(linters: &Vec<Linter>) -> Vec<Command> {
+fn generate_linter_cmds(linters: &Vec<Linter>, manager: PackageManager) -> Vec<Command> {
     let mut commands = vec![];
     for linter in linters {
-        commands.append(&mut linter.get_install_commands());
+        commands.append(&mut linter.get_install_commands(manager));
     }
     commands
 }
 
-fn create_repo() {
+fn create_repo() -> Vec<Command> {
     println!("Creating git repo...");
+    let mut commands = vec![];
+
     let mut command = Command::new("git");
-    command.args(&["init"]);
-    command.output().expect("Failed to create git repo");
+    command.args(["init"]);
+    commands.push(command);
 
     let mut command = Command::new("git");
-    command.args(&["checkout", "-b", "main"]);
-    command.output().expect("Failed to create main branch");
+    command.args(["checkout", "-b", "main"]);
+    commands.push(command);
 
-    println!("Creating initial commit...");
     let mut command = Command::new("git");
-    command.args(&["add", "."]);
-    command.output().expect("Failed to add files to git repo");
+    command.args(["add", "."]);
+    commands.push(command);
 
     let mut command = Command::new("git");
-    command.args(&["commit", "-m", "Initial commit"]);
-    command.output().expect("Failed to create initial commit");
+    command.args(["commit", "-m", "Initial commit"]);
+    commands.push(command);
+
+    commands
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use tempfile::TempDir;
+
+    use super::*;
+    use crate::{
+        config::TemplateEngine,
+        dialogue::{SpaFramework, UserOptions},
+        runner::{RecordedCommand, RecordingRunner, ScriptedRunner},
+    };
+
+    fn options(out: &TempDir, stack: &str, db: Option<&str>, orm: bool) -> UserOptions {
+        let containers = matches!(stack, "rsapi" | "tsapi");
+        UserOptions {
+            stack: stack.to_string(),
+            output_dir: out.path().join("test-app"),
+            app_name: "test-app".to_string(),
+            db: db.map(|db| db.to_string()),
+            orm,
+            db_client: None,
+            spa: None,
+            template_engine: None,
+            containers,
+            package_manager: PackageManager::Bun,
+        }
+    }
+
+    fn build(options: UserOptions) -> Vec<RecordedCommand> {
+        let config = ScaffoldConfig::new(options, Path::new("templates")).unwrap();
+        let mut runner = RecordingRunner::default();
+        let report =
+            ProjectBuilder::build(&config, &mut runner, build_options(FailurePolicy::Abort))
+                .expect("Failed to build project");
+        assert_eq!(report.failures().count(), 0);
+        runner.commands
+    }
+
+    fn build_options(policy: FailurePolicy) -> BuildOptions {
+        BuildOptions {
+            policy,
+            keep_on_failure: false,
+        }
+    }
+
+    // rscli runs `cargo init` then `cargo add dialoguer` as its first two commands
+    // every rscli module, installed in a single batch
+    const RSCLI_INSTALL: &str = "cargo add dialoguer clap console indicatif strum serde ratatui \
+        --features clap/derive,strum/derive,strum/strum_macros,ratatui/all-widgets";
+
+    fn build_failing_rscli(out: &TempDir, build: BuildOptions) -> (BuildReport, Vec<String>) {
+        let config =
+            ScaffoldConfig::new(options(out, "rscli", None, false), Path::new("templates"))
+                .unwrap();
+        let mut runner = ScriptedRunner::default()
+            .respond(0, "")
+            .respond(101, "")
+            .respond(101, "");
+        let report =
+            ProjectBuilder::build(&config, &mut runner, build).expect("Failed to build project");
+        (report, lines(&runner.commands))
+    }
+
+    fn lines(commands: &[RecordedCommand]) -> Vec<String> {
+        commands.iter().map(|command| command.line()).collect()
+    }
+
+    #[test]
+    fn builds_every_stack_db_orm_combination() {
+        let dbs = [None, Some("postgres"), Some("mongo"), Some("sqlite")];
+        for stack in ["rsapi", "rscli", "tsapi", "tscli"] {
+            let rust = stack.starts_with("rs");
+            for db in dbs.iter() {
+                for orm in [false, true] {
+                    if rust && orm && matches!(db, Some("mongo")) {
+                        continue;
+                    }
+                    let out = TempDir::new().unwrap();
+                    let root = out.path().join("test-app");
+                    let commands = build(options(&out, stack, *db, orm));
+                    let lines = lines(&commands);
+
+                    let staging = StagingDir::path_for(&root);
+                    assert!(commands.iter().all(|c| c.cwd.as_deref() == Some(&*staging)));
+                    assert!(!staging.exists());
+                    assert!(root.join(answers::ANSWERS_FILE).is_file());
+                    assert_eq!(lines.last().unwrap(), "git commit -m Initial commit");
+
+                    let init = if rust {
+                        "cargo init --name test-app"
+                    } else {
+                        "bun init -y"
+                    };
+                    assert_eq!(lines[0], init);
+
+                    let client = match (db, rust, orm) {
+                        (None, _, _) => None,
+                        (Some("mongo"), _, false) => Some("mongodb"),
+                        (Some("mongo"), _, true) => Some("mongoose"),
+                        (Some(_), true, true) => Some("diesel"),
+                        (Some(_), true, false) => Some("sqlx"),
+                        (Some(_), false, true) => Some("prisma"),
+                        (Some("postgres"), false, false) => Some("slonik"),
+                        (Some(_), false, false) => Some("better-sqlite3"),
+                    };
+                    if let Some(client) = client {
+                        // the client is installed in the same batch as the stack's modules
+                        let add = if rust { "cargo add " } else { "bun add " };
+                        assert!(
+                            lines.iter().any(|line| line.starts_with(add)
+                                && line.split(' ').any(|arg| arg == client)),
+                            "{:?} {:?} orm={} is missing `{}` in {:#?}",
+                            stack,
+                            db,
+                            orm,
+                            client,
+                            lines
+                        );
+                    }
+
+                    let api = stack.ends_with("api");
+                    assert_eq!(root.join("docker-compose.yml").is_file(), api);
+                    if api && db.is_some() {
+                        let compose = std::fs::read_to_string(root.join("docker-compose.yml"))
+                            .expect("Failed to read docker-compose.yml");
+                        assert!(compose.contains("db_test:"));
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn builds_exact_rscli_sqlite_orm_plan() {
+        let out = TempDir::new().unwrap();
+        let commands = build(options(&out, "rscli", Some("sqlite"), true));
+
+        assert_eq!(
+            lines(&commands),
+            [
+                "cargo init --name test-app",
+                "cargo add dialoguer clap console indicatif strum serde ratatui diesel \
+                 --features clap/derive,strum/derive,strum/strum_macros,ratatui/all-widgets,diesel/sqlite",
+                "rustup update",
+                "rustup component add clippy",
+                "rustup component add rustfmt",
+                "git init",
+                "git checkout -b main",
+                "git add .",
+                "git commit -m Initial commit",
+            ]
+        );
+        assert_eq!(
+            commands[1].env,
+            vec![(
+                "CARGO_NET_GIT_FETCH_WITH_CLI".to_string(),
+                Some("true".to_string())
+            )]
+        );
+
+        let clippy = std::fs::read_to_string(out.path().join("test-app/clippy.toml"))
+            .expect("Failed to read clippy.toml");
+        assert!(clippy.starts_with("# Clippy settings for test-app"));
+        assert!(out.path().join("test-app/rustfmt.toml").is_file());
+    }
+
+    #[test]
+    fn stages_under_the_package_name() {
+        let out = TempDir::new().unwrap();
+        let mut options = options(&out, "tsapi", None, false);
+        options.app_name = "Test App".to_string();
+        let commands = build(options);
+
+        // init tools take their package name from the folder, so the staging leaf must be the slug
+        let staging = StagingDir::path_for(&out.path().join("test-app"));
+        assert_eq!(staging, out.path().join(".germinate-staging/test-app"));
+        assert_eq!(commands[0].cwd.as_deref(), Some(&*staging));
+        assert_eq!(
+            lines(&commands[..2]),
+            ["bun init -y", "bun pm pkg set name=test-app"]
+        );
+        assert!(!out.path().join(".germinate-staging").exists());
+    }
+
+    #[test]
+    fn rejects_output_dir_without_a_name() {
+        let out = TempDir::new().unwrap();
+        let mut options = options(&out, "rsapi", None, false);
+        options.output_dir = out.path().join("..");
+        let config = ScaffoldConfig::new(options, Path::new("templates")).unwrap();
+
+        let error = ProjectBuilder::plan(&config).unwrap_err();
+        assert!(matches!(error, GerminateError::Config(_)), "{}", error);
+    }
+
+    #[test]
+    fn adds_linter_configs_and_scripts() {
+        let out = TempDir::new().unwrap();
+        let mut options = options(&out, "tsapi", None, false);
+        options.spa = Some(SpaFramework::Svelte);
+        let lines = lines(&build(options));
+
+        assert!(lines.contains(&"bun add eslint @eslint/js typescript-eslint --dev".to_string()));
+        assert!(lines.contains(&"bun add stylelint stylelint-config-standard --dev".to_string()));
+        assert!(lines.contains(&"bun pm pkg set scripts.lint=eslint .".to_string()));
+
+        assert!(lines.contains(
+            &"bunx create-vite@latest frontend --template svelte-ts --overwrite".to_string()
+        ));
+
+        let eslint = std::fs::read_to_string(out.path().join("test-app/eslint.config.js"))
+            .expect("Failed to read eslint.config.js");
+        assert!(eslint.contains(r#"ignores: ["node_modules", "dist", "frontend"]"#));
+        assert!(out.path().join("test-app/.stylelintrc").is_file());
+    }
+
+    #[test]
+    fn runs_js_commands_through_package_manager() {
+        let out = TempDir::new().unwrap();
+        let mut options = options(&out, "tsapi", Some("postgres"), true);
+        options.spa = Some(SpaFramework::React);
+        options.package_manager = PackageManager::Npm;
+        let lines = lines(&build(options));
+
+        for line in [
+            "npm init -y",
+            "npm pkg set name=test-app",
+            "npm install @hono/node-server hono @hono/zod-validator @hono/client zod",
+            "npm install tsx prisma --save-dev",
+            "npx --yes prisma init",
+            "npm install eslint @eslint/js typescript-eslint --save-dev",
+            "npx --yes create-vite@latest frontend --template react-ts --overwrite",
+            "npm pkg set scripts.lint=eslint .",
+            "npm pkg set scripts.dev=tsx watch src/index.ts",
+        ] {
+            assert!(
+                lines.contains(&line.to_string()),
+                "missing `{}` in {:#?}",
+                line,
+                lines
+            );
+        }
+        assert!(!lines.iter().any(|line| line.contains("bun")));
+
+        // the generated server and containers run on Node too
+        let root = out.path().join("test-app");
+        let index = std::fs::read_to_string(root.join("src/index.ts")).unwrap();
+        assert!(index.contains("from \"@hono/node-server/serve-static\""));
+        assert!(index.contains("serve({ fetch: app.fetch, port: 3000 });"));
+        assert!(!index.contains("bun"));
+        let dockerfile = std::fs::read_to_string(root.join("docker/app/Dockerfile.prod")).unwrap();
+        for line in [
+            "FROM node:lts-alpine",
+            "COPY package.json package-lock.json ./",
+            "RUN npm ci",
+            "RUN cd frontend && npm install && npm run build",
+        ] {
+            assert!(
+                dockerfile.contains(line),
+                "missing `{}` in {}",
+                line,
+                dockerfile
+            );
+        }
+        let compose = std::fs::read_to_string(root.join("docker-compose.yml")).unwrap();
+        assert!(compose.contains("command: sh -c \"npm install && npm run dev -- --host 0.0.0.0\""));
+        let entrypoint =
+            std::fs::read_to_string(root.join("docker/rootfs/etc/entrypoint.prod.sh")).unwrap();
+        assert!(entrypoint.contains("npm run start"));
+    }
+
+    #[test]
+    fn serves_spa_from_rust_api() {
+        let out = TempDir::new().unwrap();
+        let mut options = options(&out, "rsapi", Some("postgres"), false);
+        options.spa = Some(SpaFramework::React);
+        let spa_lines = lines(&build(options.clone()));
+        let install = spa_lines
+            .iter()
+            .find(|line| line.starts_with("cargo add axum"))
+            .unwrap();
+        assert!(
+            install.starts_with("cargo add axum tokio serde tower-http reqwest ")
+                && install.contains(",tower-http/fs,"),
+            "{}",
+            install
+        );
+
+        let api_out = TempDir::new().unwrap();
+        options.output_dir = api_out.path().join("test-app");
+        options.spa = None;
+        let api_lines = lines(&build(options));
+        assert!(!api_lines.iter().any(|line| line.contains("reqwest")));
+
+        let root = out.path().join("test-app");
+        let main = std::fs::read_to_string(root.join("src/main.rs")).unwrap();
+        assert!(main.contains("ServeDir::new(FRONTEND_DIST)"));
+        assert!(main.contains("fn proxy_to_vite"));
+        assert!(!main.contains("{{"));
+        assert!(!root.join("src/main.rs.hbs").exists());
+
+        let compose = std::fs::read_to_string(root.join("docker-compose.yml")).unwrap();
+        assert!(compose.contains("VITE_DEV_SERVER: http://frontend:5173"));
+        assert!(compose.contains("      - frontend\n      - db\n"));
+        assert!(compose.contains("  frontend:\n    image: oven/bun:alpine"));
+        let dockerfile = std::fs::read_to_string(root.join("docker/app/Dockerfile.prod")).unwrap();
+        assert!(dockerfile.contains("COPY --from=frontend /frontend/dist ./frontend/dist"));
+    }
+
+    #[test]
+    fn adds_views_for_template_engine() {
+        // the engine's packages join the stack's install batch
+        let cases = [
+            (
+                "rsapi",
+                TemplateEngine::Handlebars,
+                &[" handlebars ", " serde_json ", "handlebars/dir_source"][..],
+                "hbs",
+            ),
+            ("rsapi", TemplateEngine::Tera, &[" tera "][..], "html"),
+            ("rsapi", TemplateEngine::Askama, &[" askama "][..], "html"),
+            (
+                "rsapi",
+                TemplateEngine::Minijinja,
+                &[" minijinja ", "minijinja/loader"][..],
+                "html",
+            ),
+            (
+                "tsapi",
+                TemplateEngine::Handlebars,
+                &[" handlebars"][..],
+                "hbs",
+            ),
+        ];
+        for (stack, engine, packages, extension) in cases {
+            let out = TempDir::new().unwrap();
+            let mut options = options(&out, stack, None, false);
+            options.template_engine = Some(engine);
+            options.spa = Some(SpaFramework::Vue);
+            let lines = lines(&build(options));
+            let install = lines
+                .iter()
+                .find(|line| {
+                    line.starts_with("cargo add axum") || line.starts_with("bun add bun-types")
+                })
+                .unwrap();
+            for package in packages {
+                assert!(
+                    install.contains(package),
+                    "{} didn't install {} in {}",
+                    engine,
+                    package,
+                    install
+                );
+            }
+            let engines = ["handlebars", "tera", "askama", "minijinja"];
+            let others = engines.iter().filter(|other| **other != engine.to_string());
+            for other in others {
+                assert!(!install.contains(&format!(" {} ", other)), "{}", install);
+            }
+
+            let root = out.path().join("test-app");
+            for view in ["layouts/main", "partials/header", "welcome"] {
+                let view = root.join("views").join(format!("{}.{}", view, extension));
+                assert!(view.is_file(), "{} is missing {}", engine, view.display());
+            }
+
+            let server = match stack {
+                "rsapi" => {
+                    let views = std::fs::read_to_string(root.join("src/views.rs")).unwrap();
+                    assert!(views.contains("/welcome"), "{} has no sample route", engine);
+                    std::fs::read_to_string(root.join("src/main.rs")).unwrap()
+                }
+                _ => std::fs::read_to_string(root.join("src/index.ts")).unwrap(),
+            };
+            assert!(server.contains("views"), "{} views aren't wired in", engine);
+        }
+
+        let out = TempDir::new().unwrap();
+        let mut options = options(&out, "tsapi", None, false);
+        options.template_engine = Some(TemplateEngine::Askama);
+        let error = ScaffoldConfig::new(options, Path::new("templates")).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Invalid options: the tsapi stack doesn't support the askama template engine (available: handlebars)"
+        );
+    }
+
+    #[test]
+    fn rejects_options_the_stack_does_not_support() {
+        let out = TempDir::new().unwrap();
+        let mut spa = options(&out, "rscli", None, false);
+        spa.spa = Some(SpaFramework::Vue);
+        let error = ScaffoldConfig::new(spa, Path::new("templates")).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Invalid options: the rscli stack doesn't support a SPA frontend"
+        );
+
+        let mut containers = options(&out, "tscli", None, false);
+        containers.containers = true;
+        let error = ScaffoldConfig::new(containers, Path::new("templates")).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Invalid options: the tscli stack doesn't support containers"
+        );
+    }
+
+    #[test]
+    fn leaves_spa_out_of_plain_api() {
+        let out = TempDir::new().unwrap();
+        build(options(&out, "tsapi", None, false));
+
+        let root = out.path().join("test-app");
+        let index = std::fs::read_to_string(root.join("src/index.ts")).unwrap();
+        assert!(!index.contains("serveStatic"));
+        assert!(!index.contains("./views"));
+        assert!(!root.join("views").exists());
+        assert!(index.contains(r#"app.get("/", (c) => c.text("Welcome to Hono!"));"#));
+        let compose = std::fs::read_to_string(root.join("docker-compose.yml")).unwrap();
+        assert!(!compose.contains("frontend"));
+        assert!(!compose.contains("depends_on"));
+    }
+
+    #[test]
+    fn aborts_on_first_failed_command() {
+        let out = TempDir::new().unwrap();
+        let (report, lines) = build_failing_rscli(&out, build_options(FailurePolicy::Abort));
+
+        let failures: Vec<_> = report.failures().collect();
+        assert_eq!(failures.len(), 1);
+        assert!(failures[0].step.ends_with(RSCLI_INSTALL));
+        assert!(failures[0].error.as_ref().unwrap().contains("code 101"));
+        assert_eq!(lines.last().unwrap(), RSCLI_INSTALL);
+        assert!(!lines.iter().any(|line| line.starts_with("git")));
+
+        // nothing is left behind for a failed build
+        assert_eq!(std::fs::read_dir(out.path()).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn keeps_staging_dir_on_failure() {
+        let out = TempDir::new().unwrap();
+        let options = BuildOptions {
+            policy: FailurePolicy::Abort,
+            keep_on_failure: true,
+        };
+        build_failing_rscli(&out, options);
+
+        let root = out.path().join("test-app");
+        let staging = StagingDir::path_for(&root);
+        assert!(!root.exists());
+        assert!(staging.join(answers::ANSWERS_FILE).is_file());
+    }
+
+    #[test]
+    fn continues_past_failed_command() {
+        let out = TempDir::new().unwrap();
+        let (report, lines) = build_failing_rscli(&out, build_options(FailurePolicy::Continue));
+
+        assert_eq!(report.failures().count(), 2);
+        assert_eq!(lines.last().unwrap(), "git commit -m Initial commit");
+
+        // the rest of the project is moved into place rather than rolled back
+        let root = out.path().join("test-app");
+        assert!(root.join(answers::ANSWERS_FILE).is_file());
+        assert!(!root.join(crate::journal::JOURNAL_DIR).exists());
+        assert!(!StagingDir::path_for(&root).exists());
+    }
+
+    #[test]
+    fn retries_failed_command() {
+        let out = TempDir::new().unwrap();
+        let (report, lines) = build_failing_rscli(&out, build_options(FailurePolicy::Retry(2)));
+
+        assert_eq!(report.failures().count(), 0);
+        let attempts = lines.iter().filter(|line| *line == RSCLI_INSTALL).count();
+        assert_eq!(attempts, 3);
+        assert_eq!(lines.last().unwrap(), "git commit -m Initial commit");
+    }
+
+    #[test]
+    fn resumes_from_first_incomplete_step() {
+        let out = TempDir::new().unwrap();
+        let build = BuildOptions {
+            policy: FailurePolicy::Abort,
+            keep_on_failure: true,
+        };
+        build_failing_rscli(&out, build);
+
+        let root = out.path().join("test-app");
+        let staging = StagingDir::path_for(&root);
+        let journal = Journal::load(&staging).expect("Failed to load journal");
+        assert!(journal.steps.iter().take(4).all(|entry| entry.done));
+        assert!(!journal.is_done(4));
+
+        let mut options = journal.options;
+        options.output_dir = root.clone();
+        let config = ScaffoldConfig::new(options, Path::new("templates")).unwrap();
+        let mut runner = RecordingRunner::default();
+        let report =
+            ProjectBuilder::resume(&config, &mut runner, build).expect("Failed to resume project");
+
+        assert_eq!(report.failures().count(), 0);
+        let lines = lines(&runner.commands);
+        assert_eq!(lines[0], RSCLI_INSTALL);
+        assert!(!lines.iter().any(|line| line.starts_with("cargo init")));
+        assert!(root.join(answers::ANSWERS_FILE).is_file());
+        assert!(!root.join(crate::journal::JOURNAL_DIR).exists());
+        assert!(!staging.exists());
+    }
 }
@@ -0,0 +1,381 @@
+use clap::{Args, Parser, Subcommand, ValueEnum};
+
+use std::path::PathBuf;
+
+use crate::{
+    builder::BuildOptions,
+    config::TemplateEngine,
+    databases::DatabaseRegistry,
+    dialogue::{SpaFramework, UserOptions},
+    error::{GerminateError, Result},
+    package_manager::PackageManager,
+    plan::FailurePolicy,
+};
+
+#[derive(Debug, Parser)]
+#[command(
+    name = "germinate",
+    version,
+    about = "A launchpad for accelerating from idea to code"
+)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Commands>,
+
+    /// Folder of stack templates, taking precedence over `GERMINATE_TEMPLATES` and the config file
+    #[arg(long, global = true, value_name = "DIR")]
+    pub templates_dir: Option<PathBuf>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Commands {
+    /// Scaffold a new project, prompting for any option not given as a flag
+    New(NewArgs),
+    /// Continue an interrupted build from its first incomplete step
+    Resume(ResumeArgs),
+    /// Print the templates directory in use and where it was configured
+    Where,
+    /// Print the JSON Schema of `stack_template.toml`, for editor completion and checks
+    Schema,
+    /// Check a stack folder for problems without building anything
+    Validate {
+        /// Folder of the stack, holding its `stack_template.toml`
+        dir: PathBuf,
+    },
+    /// Work with the stock templates built into germinate
+    #[command(subcommand)]
+    Templates(TemplatesCommand),
+}
+
+#[derive(Debug, Subcommand)]
+pub enum TemplatesCommand {
+    /// Write the built in templates to a new folder for customization
+    Export {
+        /// Folder to write the templates to (must be empty or not exist yet)
+        dir: PathBuf,
+    },
+}
+
+// Every field is optional so that unanswered questions fall back to the interactive prompts
+#[derive(Debug, Clone, Default, Args)]
+pub struct NewArgs {
+    /// Answers file (TOML or JSON) to replay; flags given alongside it take precedence
+    #[arg(long, value_name = "FILE")]
+    pub answers: Option<PathBuf>,
+
+    /// Stack to scaffold, by the name of its folder in the templates directory
+    #[arg(long)]
+    pub stack: Option<String>,
+
+    /// Name of the project (the output folder is the slugified name)
+    #[arg(long)]
+    pub name: Option<String>,
+
+    /// Database platform to install a client for, by its id in `databases.toml`
+    #[arg(long, conflicts_with = "no_db")]
+    pub db: Option<String>,
+
+    /// Database client to use when more than one fits the stack (needs `--db` or an answers file's `db`)
+    #[arg(long)]
+    pub db_client: Option<String>,
+
+    /// Skip the database question and scaffold without a database
+    #[arg(long)]
+    pub no_db: bool,
+
+    /// Use an ORM for the selected database (`--orm=false` to decline)
+    #[arg(long, num_args = 0..=1, default_missing_value = "true")]
+    pub orm: Option<bool>,
+
+    /// SPA framework to scaffold into `frontend/` with Vite (web stacks only)
+    #[arg(long, value_enum, conflicts_with = "no_spa")]
+    pub spa: Option<SpaFramework>,
+
+    /// Skip the SPA question and scaffold without a frontend framework
+    #[arg(long)]
+    pub no_spa: bool,
+
+    /// Engine for server rendered views, from the ones the stack offers (web stacks only)
+    #[arg(long, value_enum, conflicts_with = "no_template_engine")]
+    pub template_engine: Option<TemplateEngine>,
+
+    /// Skip the template engine question and scaffold without server rendered views
+    #[arg(long)]
+    pub no_template_engine: bool,
+
+    /// Generate Docker containers (web stacks only)
+    #[arg(long, num_args = 0..=1, default_missing_value = "true")]
+    pub containers: Option<bool>,
+
+    /// Package manager for every JavaScript command (JavaScript stacks and SPAs)
+    #[arg(long, value_enum)]
+    pub package_manager: Option<PackageManager>,
+
+    /// Print every folder, file and command the scaffold would run without touching the disk
+    #[arg(long)]
+    pub dry_run: bool,
+
+    #[command(flatten)]
+    pub build: BuildArgs,
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct ResumeArgs {
+    /// Output directory of the interrupted build
+    pub dir: PathBuf,
+
+    #[command(flatten)]
+    pub build: BuildArgs,
+}
+
+#[derive(Debug, Clone, Default, Args)]
+pub struct BuildArgs {
+    /// What to do when an install step fails [default: abort, or retry with `--retries`]
+    #[arg(long, value_enum)]
+    pub on_failure: Option<OnFailure>,
+
+    /// How many times to retry a failed step, implying `--on-failure retry` [default: 2]
+    #[arg(long)]
+    pub retries: Option<u32>,
+
+    /// Keep the staging directory of a failed build for debugging instead of removing it
+    #[arg(long)]
+    pub keep_on_failure: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum OnFailure {
+    Abort,
+    Continue,
+    Retry,
+}
+
+impl BuildArgs {
+    pub fn build_options(&self) -> Result<BuildOptions> {
+        let policy = match (self.on_failure, self.retries) {
+            (None, None) | (Some(OnFailure::Abort), None) => FailurePolicy::Abort,
+            (Some(OnFailure::Continue), None) => FailurePolicy::Continue,
+            (None | Some(OnFailure::Retry), retries) => FailurePolicy::Retry(retries.unwrap_or(2)),
+            (Some(policy), Some(_)) => {
+                return Err(GerminateError::Config(format!(
+                    "`--retries` only applies to `--on-failure retry`, not `{}`",
+                    policy
+                        .to_possible_value()
+                        .expect("no variant is skipped")
+                        .get_name()
+                )))
+            }
+        };
+        Ok(BuildOptions {
+            policy,
+            keep_on_failure: self.keep_on_failure,
+        })
+    }
+}
+
+impl NewArgs {
+    /// Fills every flag left unset from a loaded answers file
+    pub fn with_answers(mut self, answers: UserOptions, registry: &DatabaseRegistry) -> Self {
+        self.stack = self.stack.or(Some(answers.stack));
+        self.name = self.name.or(Some(answers.app_name));
+        match &self.db {
+            // the file's client is kept when the `--db` platform offers one by that name too
+            Some(db) => {
+                let offered = |client: &String| {
+                    registry
+                        .platform(db)
+                        .is_ok_and(|platform| platform.clients.iter().any(|c| &c.name == client))
+                };
+                self.db_client = self.db_client.take().or(answers.db_client.filter(offered));
+            }
+            None if !self.no_db => match answers.db {
+                Some(db) => {
+                    self.db = Some(db);
+                    self.db_client = self.db_client.or(answers.db_client);
+                }
+                None => self.no_db = true,
+            },
+            None => {}
+        }
+        self.orm = self.orm.or(Some(answers.orm));
+        if self.spa.is_none() && !self.no_spa {
+            match answers.spa {
+                Some(spa) => self.spa = Some(spa),
+                None => self.no_spa = true,
+            }
+        }
+        if self.template_engine.is_none() && !self.no_template_engine {
+            match answers.template_engine {
+                Some(engine) => self.template_engine = Some(engine),
+                None => self.no_template_engine = true,
+            }
+        }
+        self.containers = self.containers.or(Some(answers.containers));
+        self.package_manager = self.package_manager.or(Some(answers.package_manager));
+        self
+    }
+
+    /// Checks the flags that depend on each other, once the answers file has filled in its share
+    pub fn check(&self) -> Result<()> {
+        if self.db_client.is_some() && self.db.is_none() {
+            return Err(GerminateError::Config(
+                "`--db-client` needs a database, from `--db` or the answers file's `db`"
+                    .to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+
+    #[test]
+    fn parses_full_new_command() {
+        let cli = Cli::try_parse_from([
+            "germinate",
+            "new",
+            "--stack",
+            "rsapi",
+            "--name",
+            "my-svc",
+            "--db",
+            "postgres",
+            "--orm",
+            "--spa",
+            "react",
+            "--template-engine",
+            "minijinja",
+            "--containers",
+            "--package-manager",
+            "pnpm",
+        ])
+        .expect("Failed to parse args");
+
+        let Some(Commands::New(args)) = cli.command else {
+            panic!("Expected new command");
+        };
+        assert_eq!(args.stack.as_deref(), Some("rsapi"));
+        assert_eq!(args.name.as_deref(), Some("my-svc"));
+        assert_eq!(args.db.as_deref(), Some("postgres"));
+        assert_eq!(args.orm, Some(true));
+        assert_eq!(args.spa, Some(SpaFramework::React));
+        assert_eq!(args.containers, Some(true));
+        assert_eq!(args.package_manager, Some(PackageManager::Pnpm));
+        assert_eq!(args.template_engine, Some(TemplateEngine::Minijinja));
+    }
+
+    #[test]
+    fn parses_explicit_false_flags() {
+        let cli = Cli::try_parse_from([
+            "germinate",
+            "new",
+            "--no-db",
+            "--orm=false",
+            "--no-spa",
+            "--no-template-engine",
+        ])
+        .expect("Failed to parse args");
+
+        let Some(Commands::New(args)) = cli.command else {
+            panic!("Expected new command");
+        };
+        assert!(args.no_db);
+        assert_eq!(args.orm, Some(false));
+        assert!(args.no_spa);
+        assert!(args.no_template_engine);
+    }
+
+    #[test]
+    fn parses_global_templates_dir() {
+        let cli =
+            Cli::try_parse_from(["germinate", "where", "--templates-dir", "shared/templates"])
+                .expect("Failed to parse args");
+
+        assert!(matches!(cli.command, Some(Commands::Where)));
+        assert_eq!(cli.templates_dir, Some(PathBuf::from("shared/templates")));
+    }
+
+    #[test]
+    fn parses_templates_export() {
+        let cli = Cli::try_parse_from(["germinate", "templates", "export", "my-templates"])
+            .expect("Failed to parse args");
+
+        let Some(Commands::Templates(TemplatesCommand::Export { dir })) = cli.command else {
+            panic!("Expected templates export command");
+        };
+        assert_eq!(dir, PathBuf::from("my-templates"));
+    }
+
+    #[test]
+    fn checks_db_client_after_answers() {
+        let cli = Cli::try_parse_from(["germinate", "new", "--db-client", "sqlx"])
+            .expect("Failed to parse args");
+        let Some(Commands::New(args)) = cli.command else {
+            panic!("Expected new command");
+        };
+        assert!(args.check().is_err());
+
+        let answers: UserOptions =
+            toml::from_str("stack = \"rsapi\"\napp_name = \"svc\"\ndb = \"postgres\"\n")
+                .expect("Failed to parse answers");
+        let registry = DatabaseRegistry::load(Path::new("templates")).unwrap();
+        let args = args.with_answers(answers, &registry);
+        assert!(args.check().is_ok());
+        assert_eq!(args.db_client.as_deref(), Some("sqlx"));
+    }
+
+    #[test]
+    fn keeps_answered_db_client_for_db_flag() {
+        let registry = DatabaseRegistry::load(Path::new("templates")).unwrap();
+        let with_answers = |db: &str| {
+            let cli = Cli::try_parse_from(["germinate", "new", "--db", db])
+                .expect("Failed to parse args");
+            let Some(Commands::New(args)) = cli.command else {
+                panic!("Expected new command");
+            };
+            let answers: UserOptions = toml::from_str(
+                "stack = \"rsapi\"\napp_name = \"svc\"\ndb = \"postgres\"\norm = true\ndb_client = \"diesel\"\n",
+            )
+            .expect("Failed to parse answers");
+            args.with_answers(answers, &registry).db_client
+        };
+
+        assert_eq!(with_answers("postgres").as_deref(), Some("diesel"));
+        assert_eq!(with_answers("sqlite").as_deref(), Some("diesel"));
+        assert_eq!(with_answers("mongo"), None);
+    }
+
+    #[test]
+    fn retries_imply_retry_policy() {
+        let build_args = |args: &[&str]| {
+            let cli = Cli::try_parse_from(["germinate", "new"].iter().chain(args))
+                .expect("Failed to parse args");
+            let Some(Commands::New(args)) = cli.command else {
+                panic!("Expected new command");
+            };
+            args.build
+        };
+
+        let options = build_args(&["--retries", "5"]).build_options().unwrap();
+        assert!(matches!(options.policy, FailurePolicy::Retry(5)));
+        let options = build_args(&["--on-failure", "retry"])
+            .build_options()
+            .unwrap();
+        assert!(matches!(options.policy, FailurePolicy::Retry(2)));
+        let options = build_args(&[]).build_options().unwrap();
+        assert!(matches!(options.policy, FailurePolicy::Abort));
+        assert!(build_args(&["--on-failure", "continue", "--retries", "5"])
+            .build_options()
+            .is_err());
+    }
+
+    #[test]
+    fn rejects_db_with_no_db() {
+        let result = Cli::try_parse_from(["germinate", "new", "--db", "mongo", "--no-db"]);
+        assert!(result.is_err());
+    }
+}
@@ -1,34 +1,66 @@
-use std::{collections::HashMap, env, path::PathBuf, vec};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use clap::ValueEnum;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use strum::{Display, EnumString, EnumVariantNames};
 
 use crate::{
-    db_client::DbClient,
-    dialogue::StackTemplate,
-    dialogue::{Database, UserOptions},
-    linters::Linter,
+    databases::{DatabasePlatform, DatabaseRegistry, DbClient},
+    dialogue::UserOptions,
+    error::{GerminateError, Result},
+    linters::{Linter, LinterRegistry},
     module::Module,
-    toml_parser::TomlTemplate,
+    stack::Stack,
 };
 
 type NpmDeps = Vec<Module>;
 type CargoDeps = Vec<Module>;
 pub type PackageScripts = HashMap<String, String>;
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, EnumString, EnumVariantNames, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+#[strum(serialize_all = "lowercase")]
 pub enum Language {
     Rust,
     JavaScript,
     TypeScript,
 }
 
+/// Engines for server rendered views, each with its own `template_engine/<name>/` folder in the stack
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Display,
+    EnumString,
+    EnumVariantNames,
+    ValueEnum,
+    Serialize,
+    Deserialize,
+    JsonSchema,
+)]
+#[serde(rename_all = "lowercase")]
+#[strum(serialize_all = "lowercase")]
+pub enum TemplateEngine {
+    Handlebars,
+    Tera,
+    Askama,
+    Minijinja,
+}
+
 #[allow(unused)]
 #[derive(Debug, Clone)]
 pub struct ScaffoldConfig {
     pub user_options: UserOptions,
     pub title: String,
     pub root_dir: PathBuf,
-    pub template_dir: PathBuf,
-    pub languages: Vec<Language>,
-    pub db: Option<Database>,
+    pub stack: Stack,
+    pub db: Option<DatabasePlatform>,
     pub db_client: Option<DbClient>,
     pub linters: Vec<Linter>,
     pub npm_scripts: Option<PackageScripts>,
@@ -40,16 +72,12 @@ pub struct ScaffoldConfig {
 }
 
 impl ScaffoldConfig {
-    pub fn new(options: UserOptions) -> Self {
+    /// Builds the config from the stack's folder inside `templates_dir`
+    pub fn new(options: UserOptions, templates_dir: &Path) -> Result<Self> {
         let title = options.app_name.clone();
         let root_dir = PathBuf::from(&options.output_dir);
-        let template_dir = env::current_exe()
-            .unwrap()
-            .parent()
-            .unwrap()
-            .join(options.stack.get_path().parent().unwrap());
-
-        let toml = TomlTemplate::new(&template_dir.join("stack_template.toml"));
+        let stack = Stack::find(templates_dir, &options.stack)?;
+        let toml = &stack.template;
         let subfolders = toml.get_subfolders().cloned();
         let dependencies = toml.get_dependencies();
         let scripts = match toml.get_scripts() {
@@ -60,72 +88,81 @@ impl ScaffoldConfig {
         let npm_scripts = scripts.get("npm").cloned();
         let cargo_scripts = scripts.get("cargo").cloned();
 
-        let npm_deps = dependencies.get("npm").unwrap().clone();
-        let cargo_deps = dependencies.get("cargo").unwrap().clone();
-
-        let db = options.db.clone();
-
-        let db_client = match &db {
-            Some(db_platform) => match db_platform {
-                Database::Postgres => match options.stack {
-                    StackTemplate::RSAPI | StackTemplate::RSCLI => match options.orm {
-                        true => Some(DbClient::Diesel),
-                        false => Some(DbClient::Sqlx),
-                    },
-                    _ => match options.orm {
-                        true => Some(DbClient::Prisma),
-                        false => Some(DbClient::Slonik),
-                    },
-                },
-                Database::Sqlite => match options.stack {
-                    StackTemplate::RSAPI | StackTemplate::RSCLI => match options.orm {
-                        true => Some(DbClient::Diesel),
-                        false => Some(DbClient::Sqlx),
-                    },
-                    _ => match options.orm {
-                        true => Some(DbClient::Prisma),
-                        false => Some(DbClient::BetterSqlite),
-                    },
-                },
-                Database::Mongo => match options.stack {
-                    StackTemplate::RSAPI | StackTemplate::RSCLI => match options.orm {
-                        true => panic!("No Rust ORM for MongoDB"),
-                        false => Some(DbClient::MongoDb),
-                    },
-                    _ => match options.orm {
-                        true => Some(DbClient::Mongoose),
-                        false => Some(DbClient::MongoDb),
-                    },
-                },
-            },
-            None => None,
+        // deps with a `when` only go in projects that made that choice
+        let needed = |deps: Vec<Module>| {
+            deps.into_iter()
+                .filter(|dep| dep.when.is_none_or(|when| when.holds(&options)))
+                .collect::<Vec<_>>()
         };
+        let npm_deps = dependencies.get("npm").cloned().flatten().map(needed);
+        let cargo_deps = dependencies.get("cargo").cloned().flatten().map(needed);
 
-        let languages = match options.stack {
-            StackTemplate::RSAPI => vec![Language::Rust],
-            StackTemplate::RSCLI => vec![Language::Rust],
-            _ => vec![Language::TypeScript, Language::JavaScript],
+        let (db, db_client) = match &options.db {
+            Some(id) => {
+                if !stack.supports_db {
+                    return Err(GerminateError::Config(format!(
+                        "the {} stack doesn't support a database",
+                        stack.id
+                    )));
+                }
+                let registry = DatabaseRegistry::load(templates_dir)?;
+                let platform = registry.platform(id)?;
+                let client =
+                    platform.select_client(&stack, options.orm, options.db_client.as_deref())?;
+                (Some(platform.clone()), Some(client.clone()))
+            }
+            None => (None, None),
         };
 
-        let linters = match options.stack {
-            StackTemplate::TSCLI => vec![Linter::ESLint],
-            StackTemplate::TSAPI => match options.spa || options.template_engine {
-                true => {
-                    vec![Linter::ESLint, Linter::Stylelint]
-                }
-                false => {
-                    vec![Linter::ESLint]
+        if let Some(engine) = options.template_engine {
+            if !stack.template_engines.contains(&engine) {
+                let available = stack
+                    .template_engines
+                    .iter()
+                    .map(|engine| engine.to_string())
+                    .collect::<Vec<_>>();
+                return Err(GerminateError::Config(format!(
+                    "the {} stack doesn't support the {} template engine (available: {})",
+                    stack.id,
+                    engine,
+                    available.join(", ")
+                )));
+            }
+        }
+
+        if options.spa.is_some() && !stack.supports_frontend {
+            return Err(GerminateError::Config(format!(
+                "the {} stack doesn't support a SPA frontend",
+                stack.id
+            )));
+        }
+        if options.containers && !stack.supports_containers {
+            return Err(GerminateError::Config(format!(
+                "the {} stack doesn't support containers",
+                stack.id
+            )));
+        }
+
+        let mut linter_names = stack.linters.clone();
+        if options.spa.is_some() || options.template_engine.is_some() {
+            linter_names.append(&mut stack.frontend_linters.clone());
+        }
+        let linters = LinterRegistry::load(templates_dir)?.select(&linter_names, &stack.id)?;
+
+        // linter scripts go in package.json, so only stacks with one get them
+        let mut npm_scripts = npm_scripts;
+        if npm_deps.is_some() {
+            for linter in &linters {
+                let scripts = npm_scripts.get_or_insert_with(PackageScripts::new);
+                for (name, script) in &linter.scripts {
+                    scripts.entry(name.clone()).or_insert(script.clone());
                 }
-            },
-            StackTemplate::RSCLI | StackTemplate::RSAPI => {
-                vec![Linter::Clippy]
             }
-        };
+        }
 
-        Self {
+        Ok(Self {
             title,
             root_dir,
-            languages,
             db,
             db_client,
             linters,
@@ -136,11 +173,49 @@ impl ScaffoldConfig {
             subfolders,
             containers: options.containers,
             user_options: options.clone(),
-            template_dir,
+            stack,
+        })
+    }
+
+    /// A copy of the config that builds into `root_dir` rather than the output directory
+    pub fn with_root(&self, root_dir: &Path) -> Self {
+        Self {
+            root_dir: root_dir.to_path_buf(),
+            ..self.clone()
+        }
+    }
+
+    /// The absolute folder the project ends up in, whichever folder it's built in
+    pub fn project_dir(&self) -> Result<PathBuf> {
+        std::path::absolute(&self.user_options.output_dir).map_err(GerminateError::io(format!(
+            "Failed to resolve the output directory {}",
+            self.user_options.output_dir.display()
+        )))
+    }
+
+    /// The name `cargo init` and `npm init` give the package, the last folder of the output directory
+    pub fn package_name(&self) -> Result<&str> {
+        let output_dir = &self.user_options.output_dir;
+        output_dir
+            .file_name()
+            .and_then(|name| name.to_str())
+            .ok_or_else(|| {
+                GerminateError::Config(format!(
+                    "the output directory `{}` doesn't end in a UTF-8 folder name",
+                    output_dir.display()
+                ))
+            })
+    }
+
+    /// The compose services for the chosen database client, if there are any
+    pub fn database_services(&self) -> Option<String> {
+        match (&self.db, &self.db_client) {
+            (Some(db), Some(client)) => db.compose_for(client),
+            _ => None,
         }
     }
 
     pub fn has_language(&self, language: &Language) -> bool {
-        self.languages.contains(language)
+        self.stack.has_language(language)
     }
 }
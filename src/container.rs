@@ -1,7 +1,12 @@
 use serde::{Deserialize, Serialize};
-use std::{env, fs};
 
-use crate::{config::ScaffoldConfig, dialogue::Database, template_generator};
+use crate::{
+    config::ScaffoldConfig,
+    error::{GerminateError, Result},
+    package_manager::{self, PackageManager},
+    plan::Step,
+    template_generator,
+};
 
 pub struct ContainerBuilder {
     config: ScaffoldConfig,
@@ -14,8 +19,8 @@ impl ContainerBuilder {
         }
     }
 
-    pub fn build(&self) {
-        generate_dockerfiles(&self.config);
+    pub fn plan(&self) -> Result<Vec<Step>> {
+        generate_dockerfiles(&self.config)
     }
 }
 
@@ -24,59 +29,71 @@ pub struct DockerVariables {
     app_name: String,
     deps_name: String, // the app name prefix of the generated deps files to remove in prod for containers
     database: bool,
-    postgres: bool,
-    mongo: bool,
-    sqlite: bool,
+    database_services: String, // compose services for the chosen database, from databases.toml
+    spa: bool,                 // adds the Vite dev server service and the frontend build
+    package_manager: PackageManager,
+    js_image: String, // the image with the package manager, and the runtime for JS stacks
+    corepack: bool,   // pnpm and yarn come with node through corepack, once it's enabled
+    lockfile: String, // COPY source for the package manager's lockfile
+    frozen_install: String, // installs the lockfile's versions for prod images
+    vite_dev: String, // runs the Vite dev server where other containers can reach it
 }
 
 impl crate::template_generator::TemplateData for DockerVariables {}
 
 impl DockerVariables {
-    pub fn new(db: &Option<Database>) -> Self {
-        let kebab_name = env::current_dir()
-            .unwrap()
-            .file_name()
-            .unwrap()
-            .to_str()
-            .unwrap()
-            .to_string();
-
-        let snake_name = kebab_name.replace("-", "_");
+    pub fn new(
+        package_name: &str,
+        database_services: Option<String>,
+        spa: bool,
+        package_manager: PackageManager,
+    ) -> Self {
+        let snake_name = package_name.replace("-", "_");
+        let js_image = match package_manager {
+            PackageManager::Bun => "oven/bun:alpine",
+            _ => "node:lts-alpine",
+        };
         Self {
-            app_name: kebab_name,
+            app_name: package_name.to_string(),
             deps_name: snake_name,
-            database: db.is_some(),
-            postgres: db
-                .as_ref()
-                .is_some_and(|db| matches!(db, Database::Postgres)),
-            mongo: db.as_ref().is_some_and(|db| matches!(db, Database::Mongo)),
-            sqlite: db.as_ref().is_some_and(|db| matches!(db, Database::Sqlite)),
+            database: database_services.is_some(),
+            database_services: database_services.unwrap_or_default(),
+            spa,
+            package_manager,
+            js_image: js_image.to_string(),
+            corepack: matches!(package_manager, PackageManager::Pnpm | PackageManager::Yarn),
+            lockfile: package_manager.lockfile().to_string(),
+            frozen_install: package_manager::line(&package_manager.install_frozen()),
+            vite_dev: package_manager::line(&package_manager.run(["dev", "--host", "0.0.0.0"])),
         }
     }
 }
 
-fn generate_dockerfiles(config: &ScaffoldConfig) {
+fn generate_dockerfiles(config: &ScaffoldConfig) -> Result<Vec<Step>> {
     println!("Generating Docker files...");
-
-    template_generator::generate_dir(
-        config.template_dir.join("docker"),
-        env::current_dir().unwrap().join("docker"),
-        &DockerVariables::new(&config.db),
-        true,
-    );
-
-    println!("Moving docker-compose.yml to project root...");
-    fs::copy(
-        env::current_dir()
-            .unwrap()
-            .join("docker/docker-compose.yml"),
-        env::current_dir().unwrap().join("docker-compose.yml"),
-    )
-    .expect("Failed to copy docker-compose.yml to project root");
-    fs::remove_file(
-        env::current_dir()
-            .unwrap()
-            .join("docker/docker-compose.yml"),
+    let docker_dir = config.stack.dir.join("docker");
+    let mut steps = template_generator::plan_dir(
+        &docker_dir,
+        &config.root_dir.join("docker"),
+        &DockerVariables::new(
+            config.package_name()?,
+            config.database_services(),
+            config.user_options.spa.is_some(),
+            config.user_options.package_manager,
+        ),
     )
-    .expect("Failed to remove docker-compose.yml from docker directory");
+    .map_err(GerminateError::io(format!(
+        "Failed to read docker templates in {}",
+        docker_dir.display()
+    )))?;
+
+    // docker-compose.yml belongs at the project root rather than in the docker folder
+    for step in steps.iter_mut() {
+        if let Step::RenderFile { dest, .. } = step {
+            if dest.ends_with("docker/docker-compose.yml") {
+                *dest = config.root_dir.join("docker-compose.yml");
+            }
+        }
+    }
+    Ok(steps)
 }
@@ -0,0 +1,327 @@
+use std::{path::Path, process::Command};
+
+use serde::Deserialize;
+use toml::{Spanned, Value};
+
+use crate::{
+    config::Language,
+    error::{GerminateError, Result},
+    module::{self, CargoDependency, Module, NpmDependency, ThenCommands},
+    package_manager::PackageManager,
+    stack::Stack,
+    toml_parser,
+};
+
+pub const DATABASES_FILE: &str = "databases.toml";
+
+/// Every database platform in the templates directory's `databases.toml`, in file order
+#[derive(Debug, Clone, Default)]
+pub struct DatabaseRegistry {
+    pub platforms: Vec<DatabasePlatform>,
+}
+
+#[derive(Debug, Clone)]
+pub struct DatabasePlatform {
+    pub id: String,
+    pub label: String,
+    /// docker-compose services for the database, used unless the client brings its own
+    pub compose: Option<String>,
+    pub clients: Vec<DbClient>,
+}
+
+/// A driver or ORM for one platform in one language
+#[derive(Debug, Clone)]
+pub struct DbClient {
+    pub name: String,
+    pub language: Language,
+    pub orm: bool,
+    pub deps: Vec<Module>,
+    pub init: ThenCommands,
+    pub compose: Option<String>,
+}
+
+/// `databases.toml` as written, before each client's deps are read in its language's format
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct DatabasesFile {
+    #[serde(default)]
+    platform: Vec<PlatformEntry>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PlatformEntry {
+    id: String,
+    label: Option<String>,
+    compose: Option<String>,
+    #[serde(default)]
+    client: Vec<ClientEntry>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ClientEntry {
+    name: String,
+    language: Language,
+    #[serde(default)]
+    orm: bool,
+    // cargo or npm dependencies depending on `language`, so they're read once it's known
+    deps: Option<Spanned<Value>>,
+    #[serde(default, deserialize_with = "module::then_commands")]
+    init: Option<ThenCommands>,
+    compose: Option<String>,
+}
+
+impl DatabaseRegistry {
+    /// Loads `databases.toml` from the templates directory. Without one, no databases are offered
+    pub fn load(templates_dir: &Path) -> Result<Self> {
+        let path = templates_dir.join(DATABASES_FILE);
+        if !path.is_file() {
+            return Ok(Self::default());
+        }
+        let source = toml_parser::read_file(&path, "database registry")?;
+        let file = toml_parser::deserialize_template::<DatabasesFile>(&path, &source)?;
+
+        let mut platforms = vec![];
+        for (index, platform) in file.platform.into_iter().enumerate() {
+            let mut clients = vec![];
+            for (client_index, client) in platform.client.into_iter().enumerate() {
+                let key = format!("platform[{}].client[{}].deps", index, client_index);
+                let deps = match (&client.deps, &client.language) {
+                    (Some(deps), Language::Rust) => {
+                        toml_parser::deserialize_deps::<CargoDependency>(
+                            &path, &source, &key, deps,
+                        )?
+                    }
+                    (Some(deps), _) => {
+                        toml_parser::deserialize_deps::<NpmDependency>(&path, &source, &key, deps)?
+                    }
+                    (None, _) => vec![],
+                };
+                clients.push(DbClient {
+                    name: client.name,
+                    language: client.language,
+                    orm: client.orm,
+                    deps,
+                    init: client.init.unwrap_or_default(),
+                    compose: client.compose,
+                });
+            }
+            platforms.push(DatabasePlatform {
+                label: platform.label.unwrap_or_else(|| platform.id.clone()),
+                id: platform.id,
+                compose: platform.compose,
+                clients,
+            });
+        }
+        Ok(Self { platforms })
+    }
+
+    pub fn platform(&self, id: &str) -> Result<&DatabasePlatform> {
+        self.platforms
+            .iter()
+            .find(|platform| platform.id == id)
+            .ok_or_else(|| {
+                let available = self
+                    .platforms
+                    .iter()
+                    .map(|platform| platform.id.as_str())
+                    .collect::<Vec<_>>();
+                GerminateError::Config(format!(
+                    "no database named `{}` (available: {})",
+                    id,
+                    available.join(", ")
+                ))
+            })
+    }
+}
+
+impl DatabasePlatform {
+    /// Clients written in one of the stack's languages, with or without an ORM
+    pub fn clients_for(&self, stack: &Stack, orm: bool) -> Vec<&DbClient> {
+        self.clients
+            .iter()
+            .filter(|client| client.orm == orm && stack.has_language(&client.language))
+            .collect()
+    }
+
+    /// Picks the named client, or the first one that fits the stack when no name is given
+    pub fn select_client(&self, stack: &Stack, orm: bool, name: Option<&str>) -> Result<&DbClient> {
+        let clients = self.clients_for(stack, orm);
+        let kind = if orm { "ORM" } else { "driver" };
+        let client = match name {
+            Some(name) => clients.into_iter().find(|client| client.name == name),
+            None => clients.into_iter().next(),
+        };
+        client.ok_or_else(|| {
+            GerminateError::Config(match name {
+                Some(name) => format!(
+                    "there is no {} {} named `{}` for the {} stack",
+                    self.label, kind, name, stack.id
+                ),
+                None => format!(
+                    "there is no {} {} for the {} stack",
+                    self.label, kind, stack.id
+                ),
+            })
+        })
+    }
+
+    /// The compose services for the chosen client
+    pub fn compose_for(&self, client: &DbClient) -> Option<String> {
+        client.compose.clone().or_else(|| self.compose.clone())
+    }
+}
+
+impl DbClient {
+    /// Commands run once the client's modules are installed with the rest of the stack's
+    pub fn get_init_commands(&self, manager: PackageManager) -> Vec<Command> {
+        module::generate_then_cmds(&self.init, manager)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::package_manager;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn selects_stock_clients() {
+        let registry = DatabaseRegistry::load(Path::new("templates")).unwrap();
+        let ids = registry
+            .platforms
+            .iter()
+            .map(|platform| platform.id.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(ids, ["postgres", "mongo", "sqlite"]);
+
+        let rsapi = Stack::find(Path::new("templates"), "rsapi").unwrap();
+        let tsapi = Stack::find(Path::new("templates"), "tsapi").unwrap();
+        let mongo = registry.platform("mongo").unwrap();
+        assert!(mongo.select_client(&rsapi, true, None).is_err());
+        assert_eq!(
+            mongo.select_client(&tsapi, true, None).unwrap().name,
+            "mongoose"
+        );
+
+        let postgres = registry.platform("postgres").unwrap();
+        let prisma = postgres.select_client(&tsapi, true, None).unwrap();
+        assert_eq!(
+            (prisma.deps[0].name.as_str(), prisma.deps[0].dev),
+            ("prisma", true)
+        );
+        let lines = prisma
+            .get_init_commands(PackageManager::Bun)
+            .iter()
+            .map(package_manager::line)
+            .collect::<Vec<_>>();
+        assert_eq!(lines, ["bunx prisma init"]);
+        assert!(postgres
+            .compose_for(prisma)
+            .unwrap()
+            .contains("postgres:16"));
+
+        assert!(registry.platform("redis").is_err());
+    }
+
+    #[test]
+    fn selects_client_by_name() {
+        let templates = TempDir::new().unwrap();
+        fs::write(
+            templates.path().join(DATABASES_FILE),
+            r#"
+            [[platform]]
+            id = "postgres"
+
+            [[platform.client]]
+            name = "diesel"
+            language = "rust"
+            orm = true
+
+            [[platform.client]]
+            name = "sea-orm"
+            language = "rust"
+            orm = true
+            deps = [{ name = "sea-orm", features = ["sqlx-postgres"] }]
+            compose = "  db:\n    image: postgres:15\n"
+            "#,
+        )
+        .unwrap();
+
+        let registry = DatabaseRegistry::load(templates.path()).unwrap();
+        let rsapi = Stack::find(Path::new("templates"), "rsapi").unwrap();
+        let postgres = registry.platform("postgres").unwrap();
+        assert_eq!(postgres.label, "postgres");
+        assert_eq!(postgres.clients_for(&rsapi, true).len(), 2);
+        assert_eq!(
+            postgres.select_client(&rsapi, true, None).unwrap().name,
+            "diesel"
+        );
+
+        let sea_orm = postgres
+            .select_client(&rsapi, true, Some("sea-orm"))
+            .unwrap();
+        assert_eq!(
+            sea_orm.deps[0].features,
+            Some(vec!["sqlx-postgres".to_string()])
+        );
+        assert_eq!(
+            postgres.compose_for(sea_orm).as_deref(),
+            Some("  db:\n    image: postgres:15\n")
+        );
+        assert!(postgres.select_client(&rsapi, false, None).is_err());
+    }
+
+    #[test]
+    fn reports_unknown_client_language() {
+        let templates = TempDir::new().unwrap();
+        fs::write(
+            templates.path().join(DATABASES_FILE),
+            "[[platform]]\nid = \"postgres\"\n\n[[platform.client]]\nname = \"psycopg\"\nlanguage = \"python\"\n",
+        )
+        .unwrap();
+
+        let error = DatabaseRegistry::load(templates.path()).unwrap_err();
+        assert!(matches!(
+            error,
+            GerminateError::Template { ref key, location: Some((6, 12)), .. }
+                if key == "platform[0].client[0].language"
+        ));
+    }
+
+    #[test]
+    fn reports_misspelled_registry_keys() {
+        let templates = TempDir::new().unwrap();
+        fs::write(
+            templates.path().join(DATABASES_FILE),
+            "[[platform]]\nid = \"postgres\"\nlable = \"Postgres\"\n",
+        )
+        .unwrap();
+        let error = DatabaseRegistry::load(templates.path()).unwrap_err();
+        assert!(
+            error
+                .to_string()
+                .ends_with("databases.toml:3:1 at `platform[0].lable`: unknown field `lable`, expected one of `id`, `label`, `compose`, `client`; did you mean `label`?"),
+            "{}",
+            error
+        );
+
+        fs::write(
+            templates.path().join(DATABASES_FILE),
+            "[[platform]]\nid = \"postgres\"\n\n[[platform.client]]\nname = \"sqlx\"\nlanguage = \"rust\"\ndeps = [{ name = \"sqlx\", dev = true, build = true }]\n",
+        )
+        .unwrap();
+        let error = DatabaseRegistry::load(templates.path()).unwrap_err();
+        assert!(
+            matches!(
+                error,
+                GerminateError::Template { ref key, location: Some((7, 8)), .. }
+                    if key == "platform[0].client[0].deps[0]"
+            ),
+            "{}",
+            error
+        );
+    }
+}
@@ -3,163 +3,368 @@ use std::{
     str::FromStr,
 };
 
+use clap::ValueEnum;
 use dialoguer::{theme::ColorfulTheme, Confirm, Input, Select};
+use serde::{Deserialize, Serialize};
 use slug::slugify;
-use strum::{EnumIter, EnumProperty, EnumString, EnumVariantNames, IntoEnumIterator, VariantNames};
-
-#[derive(Debug, Clone, EnumVariantNames, EnumString, EnumIter, EnumProperty)]
-pub enum StackTemplate {
-    #[strum(props(Label = "TypeScript Web App"))]
-    TSAPI,
-    #[strum(props(Label = "Rust Web App"))]
-    RSAPI,
-    #[strum(props(Label = "Rust CLI Tool"))]
-    RSCLI,
-    #[strum(props(Label = "TypeScript CLI Tool"))]
-    TSCLI,
-}
-
-impl StackTemplate {
-    pub fn get_path(&self) -> PathBuf {
-        match self {
-            Self::RSAPI => PathBuf::from("templates/rsapi/stack_template.toml"),
-            Self::TSCLI => PathBuf::from("templates/tscli/stack_template.toml"),
-            Self::RSCLI => PathBuf::from("templates/rscli/stack_template.toml"),
-            Self::TSAPI => PathBuf::from("templates/tsapi/stack_template.toml"),
-        }
-    }
-}
-#[derive(Debug, Clone)]
+use strum::{EnumString, EnumVariantNames, VariantNames};
+
+use crate::{
+    cli::NewArgs,
+    config::{Language, TemplateEngine},
+    databases::{DatabasePlatform, DatabaseRegistry},
+    error::{GerminateError, Result},
+    package_manager::PackageManager,
+    stack::Stack,
+};
+
+// Serialized as the answers file, so a scaffold can be replayed with `--answers`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct UserOptions {
-    pub stack: StackTemplate,
+    /// Id of the stack, the name of its folder in the templates directory
+    pub stack: String,
+    #[serde(skip)]
     pub output_dir: PathBuf,
     pub app_name: String,
-    pub db: Option<Database>,
+    /// Id of a platform in the templates directory's `databases.toml`
+    #[serde(default)]
+    pub db: Option<String>,
+    #[serde(default)]
     pub orm: bool,
-    pub spa: bool,
-    pub template_engine: bool,
+    /// Name of the client, recorded when more than one fits the stack
+    #[serde(default)]
+    pub db_client: Option<String>,
+    #[serde(default, deserialize_with = "spa_from_answers")]
+    pub spa: Option<SpaFramework>,
+    #[serde(default, deserialize_with = "template_engine_from_answers")]
+    pub template_engine: Option<TemplateEngine>,
+    #[serde(default)]
     pub containers: bool,
+    /// Runs every JavaScript install, exec and `package.json` command
+    #[serde(default)]
+    pub package_manager: PackageManager,
+}
+
+/// SPA frameworks scaffolded into `frontend/` with create-vite
+#[derive(
+    Debug, Clone, Copy, PartialEq, EnumVariantNames, EnumString, ValueEnum, Serialize, Deserialize,
+)]
+#[serde(rename_all = "lowercase")]
+#[strum(serialize_all = "lowercase")]
+pub enum SpaFramework {
+    Vue,
+    React,
+    Svelte,
+    Solid,
+}
+
+impl SpaFramework {
+    /// The create-vite `--template` for the framework
+    pub fn vite_template(&self) -> &'static str {
+        match self {
+            Self::Vue => "vue-ts",
+            Self::React => "react-ts",
+            Self::Svelte => "svelte-ts",
+            Self::Solid => "solid-ts",
+        }
+    }
+}
+
+// answers files written before the framework choice record `spa` as a boolean, which meant Vue
+fn spa_from_answers<'de, D>(deserializer: D) -> std::result::Result<Option<SpaFramework>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Spa {
+        Enabled(bool),
+        Framework(SpaFramework),
+    }
+
+    Ok(match Option::<Spa>::deserialize(deserializer)? {
+        Some(Spa::Enabled(true)) => Some(SpaFramework::Vue),
+        Some(Spa::Framework(framework)) => Some(framework),
+        Some(Spa::Enabled(false)) | None => None,
+    })
+}
+
+// answers files written before the engine choice record `template_engine` as a boolean, which meant handlebars
+fn template_engine_from_answers<'de, D>(
+    deserializer: D,
+) -> std::result::Result<Option<TemplateEngine>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Engine {
+        Enabled(bool),
+        Engine(TemplateEngine),
+    }
+
+    Ok(match Option::<Engine>::deserialize(deserializer)? {
+        Some(Engine::Enabled(true)) => Some(TemplateEngine::Handlebars),
+        Some(Engine::Engine(engine)) => Some(engine),
+        Some(Engine::Enabled(false)) | None => None,
+    })
 }
 
-pub fn get_user_config() -> Result<UserOptions, std::io::Error> {
-    let stack = get_stack();
-    let (spa, template_engine) = get_frontend(&stack);
-    let app_name = get_app_name();
+/// Builds the user options from the CLI flags, prompting for any question a flag left unanswered
+pub fn get_user_config(args: &NewArgs, templates_dir: &Path) -> Result<UserOptions> {
+    let stack = match &args.stack {
+        Some(id) => Stack::find(templates_dir, id)?,
+        None => get_stack(templates_dir)?,
+    };
+    let spa = match (args.spa, args.no_spa) {
+        (Some(spa), _) => Some(spa),
+        (None, true) => None,
+        (None, false) => match stack.supports_frontend {
+            true => get_spa()?,
+            false => None,
+        },
+    };
+    let template_engine = match (args.template_engine, args.no_template_engine) {
+        (Some(engine), _) => Some(engine),
+        (None, true) => None,
+        (None, false) => match stack.supports_frontend && !stack.template_engines.is_empty() {
+            true => get_template_engine(&stack)?,
+            false => None,
+        },
+    };
+    let app_name = or_prompt(args.name.clone(), get_app_name)?;
     let output_dir = slugify(&app_name);
-    let db = get_db();
-    let orm = match &db {
-        Some(db) => match db {
-            Database::Mongo => match stack {
-                StackTemplate::RSCLI | StackTemplate::RSAPI => false,
-                _ => get_orm(),
-            },
-            _ => get_orm(),
+    if output_dir.is_empty() {
+        return Err(GerminateError::Config(format!(
+            "the project name `{}` has no letters or digits to name its folder after",
+            app_name
+        )));
+    }
+    let registry = DatabaseRegistry::load(templates_dir)?;
+    let db = match (&args.db, args.no_db) {
+        (Some(db), _) => Some(registry.platform(db)?),
+        (None, true) => None,
+        (None, false) => match stack.supports_db && !registry.platforms.is_empty() {
+            true => get_db(&registry)?,
+            false => None,
         },
-        None => false,
+    };
+    let (orm, db_client) = match db {
+        Some(db) => get_db_client(db, &stack, args)?,
+        None => (false, None),
+    };
+
+    // an explicit answer is kept so that ScaffoldConfig can reject it for stacks without containers
+    let containers = match (args.containers, stack.supports_containers) {
+        (Some(containers), _) => containers,
+        (None, true) => containers_prompt()?,
+        (None, false) => false,
     };
 
-    let containers = match stack {
-        StackTemplate::RSAPI | StackTemplate::TSAPI => containers_prompt(),
-        _ => false,
+    // only asked when something will run through one, the SPA scaffold included
+    let uses_js = stack.has_language(&Language::TypeScript)
+        || stack.has_language(&Language::JavaScript)
+        || spa.is_some();
+    let package_manager = match uses_js {
+        true => or_prompt(args.package_manager, get_package_manager)?,
+        false => args.package_manager.unwrap_or_default(),
     };
 
     let user_config = UserOptions {
         app_name,
-        stack,
+        stack: stack.id,
         output_dir: Path::new(&output_dir).to_path_buf(),
-        db,
+        db: db.map(|db| db.id.clone()),
         orm,
+        db_client,
         spa,
         template_engine,
         containers,
+        package_manager,
     };
 
-    println!("->> User Config generated: {:?}", user_config);
-
     Ok(user_config)
 }
 
-#[derive(Debug, Clone, EnumVariantNames, EnumString)]
-pub enum Database {
-    Postgres,
-    Mongo,
-    Sqlite,
+fn or_prompt<T>(answer: Option<T>, prompt: impl FnOnce() -> Result<T>) -> Result<T> {
+    match answer {
+        Some(answer) => Ok(answer),
+        None => prompt(),
+    }
+}
+
+fn prompt_error(question: &'static str) -> impl FnOnce(std::io::Error) -> GerminateError {
+    move |e| {
+        GerminateError::input(
+            "prompt answer",
+            format!("failed to get {}: {}", question, e),
+        )
+    }
 }
 
-fn get_app_name() -> String {
+fn get_app_name() -> Result<String> {
     Input::<String>::new()
         .with_prompt("What is the name of your project?")
         .interact_text()
-        .unwrap()
+        .map_err(prompt_error("project name"))
 }
 
-fn get_stack() -> StackTemplate {
-    let mut stacks = StackTemplate::iter();
-    let prompt_labels = stacks
-        .clone()
-        .map(|s| s.get_str("Label").unwrap())
-        .collect::<Vec<_>>();
+fn get_stack(templates_dir: &Path) -> Result<Stack> {
+    let discovery = Stack::discover(templates_dir)?;
+    crate::print_warnings(&discovery.warnings);
+    let stacks = discovery.stacks;
+    if stacks.is_empty() {
+        return Err(GerminateError::Config(format!(
+            "no stacks found in {}",
+            templates_dir.display()
+        )));
+    }
+    let prompt_labels = stacks.iter().map(|s| s.label()).collect::<Vec<_>>();
     let stack_template_index = Select::with_theme(&ColorfulTheme::default())
         .with_prompt("What stack would you like to use?")
         .items(&prompt_labels)
         .interact()
-        .expect("Failed to get stack selection from user");
-    stacks.nth(stack_template_index).unwrap()
+        .map_err(prompt_error("stack selection"))?;
+    Ok(stacks[stack_template_index].clone())
 }
 
-fn get_db() -> Option<Database> {
+fn get_db(registry: &DatabaseRegistry) -> Result<Option<&DatabasePlatform>> {
     let use_db = Confirm::with_theme(&ColorfulTheme::default())
         .with_prompt("Would you like to use a database?")
         .interact()
-        .ok()?;
+        .map_err(prompt_error("database selection"))?;
 
-    let db = if use_db {
-        Some(get_db_platform())
+    if use_db {
+        Ok(Some(get_db_platform(registry)?))
     } else {
-        None
-    };
-
-    db
+        Ok(None)
+    }
 }
 
-fn get_db_platform() -> Database {
-    let db_options = Database::VARIANTS;
+fn get_db_platform(registry: &DatabaseRegistry) -> Result<&DatabasePlatform> {
+    let db_options = registry
+        .platforms
+        .iter()
+        .map(|platform| platform.label.as_str())
+        .collect::<Vec<_>>();
     let db_index = Select::with_theme(&ColorfulTheme::default())
         .with_prompt("What database would you like to use?")
         .items(&db_options)
         .interact()
-        .expect("Failed to get db selection from user");
-    <Database as FromStr>::from_str(db_options[db_index]).expect("Invalid db name")
+        .map_err(prompt_error("db selection"))?;
+    Ok(&registry.platforms[db_index])
 }
 
-fn get_orm() -> bool {
+// only asks about an ORM when the platform has both kinds of client for the stack,
+// and which client to use when more than one of that kind fits
+fn get_db_client(
+    db: &DatabasePlatform,
+    stack: &Stack,
+    args: &NewArgs,
+) -> Result<(bool, Option<String>)> {
+    let has_orm = !db.clients_for(stack, true).is_empty();
+    let has_driver = !db.clients_for(stack, false).is_empty();
+    let orm = match (has_orm, has_driver) {
+        (true, true) => or_prompt(args.orm, get_orm)?,
+        (has_orm, _) => args.orm.unwrap_or(has_orm),
+    };
+
+    let clients = db.clients_for(stack, orm);
+    let db_client = match (&args.db_client, clients.len()) {
+        (Some(name), _) => Some(name.clone()),
+        (None, 0 | 1) => None,
+        (None, _) => {
+            let names = clients
+                .iter()
+                .map(|client| client.name.as_str())
+                .collect::<Vec<_>>();
+            let index = Select::with_theme(&ColorfulTheme::default())
+                .with_prompt(format!("Which {} client would you like to use?", db.label))
+                .items(&names)
+                .interact()
+                .map_err(prompt_error("db client selection"))?;
+            Some(names[index].to_string())
+        }
+    };
+    Ok((orm, db_client))
+}
+
+fn get_orm() -> Result<bool> {
     Confirm::with_theme(&ColorfulTheme::default())
         .with_prompt("Would you like to use an ORM?")
         .interact()
-        .expect("Failed to get ORM selection from user")
+        .map_err(prompt_error("ORM selection"))
 }
 
-fn get_frontend(stack: &StackTemplate) -> (bool, bool) {
-    match stack {
-        StackTemplate::RSAPI | StackTemplate::TSAPI => {
-            let spa = Confirm::with_theme(&ColorfulTheme::default())
-                .with_prompt("Would you like to use a SPA?")
-                .interact()
-                .expect("Failed to get SPA selection from user");
+fn get_spa() -> Result<Option<SpaFramework>> {
+    let mut spa_options = vec!["none"];
+    spa_options.extend(SpaFramework::VARIANTS);
+    let spa_index = Select::with_theme(&ColorfulTheme::default())
+        .with_prompt("Would you like to use a SPA?")
+        .items(&spa_options)
+        .default(0)
+        .interact()
+        .map_err(prompt_error("SPA selection"))?;
+    match spa_index {
+        0 => Ok(None),
+        index => <SpaFramework as FromStr>::from_str(spa_options[index])
+            .map(Some)
+            .map_err(|e| GerminateError::input("SPA framework", e)),
+    }
+}
 
-            let template = Confirm::with_theme(&ColorfulTheme::default())
-                .with_prompt("Would you like to use a frontend template engine?")
-                .interact()
-                .expect("Failed to get template engine selection from user");
-            return (spa, template);
-        }
-        _ => return (false, false),
+fn get_package_manager() -> Result<PackageManager> {
+    let manager_index = Select::with_theme(&ColorfulTheme::default())
+        .with_prompt("Which package manager would you like to use?")
+        .items(PackageManager::VARIANTS)
+        .default(0)
+        .interact()
+        .map_err(prompt_error("package manager selection"))?;
+    <PackageManager as FromStr>::from_str(PackageManager::VARIANTS[manager_index])
+        .map_err(|e| GerminateError::input("package manager", e))
+}
+
+fn get_template_engine(stack: &Stack) -> Result<Option<TemplateEngine>> {
+    let mut engine_options = vec!["none".to_string()];
+    engine_options.extend(
+        stack
+            .template_engines
+            .iter()
+            .map(|engine| engine.to_string()),
+    );
+    let engine_index = Select::with_theme(&ColorfulTheme::default())
+        .with_prompt("Would you like to use a frontend template engine?")
+        .items(&engine_options)
+        .default(0)
+        .interact()
+        .map_err(prompt_error("template engine selection"))?;
+    match engine_index {
+        0 => Ok(None),
+        index => Ok(Some(stack.template_engines[index - 1])),
     }
 }
 
-fn containers_prompt() -> bool {
+fn containers_prompt() -> Result<bool> {
     Confirm::with_theme(&ColorfulTheme::default())
         .with_prompt("Would you like to use Docker containers?")
         .interact()
-        .expect("Failed to get containers selection from user")
+        .map_err(prompt_error("containers selection"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_names_without_a_slug() {
+        let args = NewArgs {
+            stack: Some("rscli".to_string()),
+            name: Some("!!!".to_string()),
+            no_db: true,
+            ..Default::default()
+        };
+        let error = get_user_config(&args, Path::new("templates")).unwrap_err();
+        assert!(matches!(error, GerminateError::Config(_)), "{}", error);
+    }
 }
@@ -0,0 +1,123 @@
+use std::{fmt, io, path::PathBuf};
+
+pub type Result<T> = std::result::Result<T, GerminateError>;
+
+#[derive(Debug)]
+pub enum GerminateError {
+    /// The selected options can't be scaffolded together
+    Config(String),
+    /// A stack template file is missing a key or has a key of the wrong shape
+    Template {
+        file: PathBuf,
+        /// Line and column of the offending key, when the file was parsed with spans
+        location: Option<(usize, usize)>,
+        key: String,
+        message: String,
+    },
+    /// `germinate validate` found problems with a stack
+    Validation { dir: PathBuf, problems: Vec<String> },
+    /// An answers file, journal or prompt answer couldn't be read
+    Input { source: String, message: String },
+    /// A filesystem or process operation failed outside of a build step
+    Io { context: String, error: io::Error },
+    /// One or more build steps failed
+    Build { failures: Vec<String> },
+}
+
+impl GerminateError {
+    pub fn template(file: &std::path::Path, key: &str, message: impl Into<String>) -> Self {
+        Self::Template {
+            file: file.to_path_buf(),
+            location: None,
+            key: key.to_string(),
+            message: message.into(),
+        }
+    }
+
+    /// Like `template`, pointing at the line and column the problem was found on
+    pub fn template_at(
+        file: &std::path::Path,
+        location: (usize, usize),
+        key: &str,
+        message: impl Into<String>,
+    ) -> Self {
+        Self::Template {
+            file: file.to_path_buf(),
+            location: Some(location),
+            key: key.to_string(),
+            message: message.into(),
+        }
+    }
+
+    pub fn input(source: impl Into<String>, message: impl ToString) -> Self {
+        Self::Input {
+            source: source.into(),
+            message: message.to_string(),
+        }
+    }
+
+    /// Wraps an io error with what germinate was doing when it happened, for use with `map_err`
+    pub fn io(context: impl Into<String>) -> impl FnOnce(io::Error) -> Self {
+        let context = context.into();
+        move |error| Self::Io { context, error }
+    }
+
+    /// Each class of error exits with its own code so scripts can tell them apart
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Self::Config(_) => 2,
+            Self::Template { .. } | Self::Validation { .. } => 3,
+            Self::Input { .. } => 4,
+            Self::Io { .. } => 5,
+            Self::Build { .. } => 6,
+        }
+    }
+}
+
+impl fmt::Display for GerminateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Config(message) => write!(f, "Invalid options: {}", message),
+            Self::Template {
+                file,
+                location,
+                key,
+                message,
+            } => {
+                write!(f, "Invalid stack template {}", file.display())?;
+                if let Some((line, column)) = location {
+                    write!(f, ":{}:{}", line, column)?;
+                }
+                if !key.is_empty() {
+                    write!(f, " at `{}`", key)?;
+                }
+                write!(f, ": {}", message)
+            }
+            Self::Validation { dir, problems } => {
+                write!(f, "Invalid stack {}:", dir.display())?;
+                for problem in problems {
+                    write!(f, "\n  {}", problem)?;
+                }
+                Ok(())
+            }
+            Self::Input { source, message } => write!(f, "Invalid {}: {}", source, message),
+            Self::Io { context, error } => write!(f, "{}: {}", context, error),
+            Self::Build { failures } => {
+                write!(f, "Project build failed:")?;
+                for failure in failures {
+                    write!(f, "\n  {}", failure)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl std::error::Error for GerminateError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io { error, .. } => Some(error),
+            _ => None,
+        }
+    }
+}
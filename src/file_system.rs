@@ -1,17 +1,24 @@
-use std::{fs, io, path::Path};
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
 
-pub fn copy_dir_all(src: impl AsRef<Path>, dst: impl AsRef<Path>) -> io::Result<()> {
-    println!("->> Copying dir: {:?} to {:?}", src.as_ref(), dst.as_ref());
-    fs::create_dir_all(&dst)?;
-    for entry in fs::read_dir(src)? {
+/// Lists every file under `src` recursively, as paths relative to `src`
+pub fn list_files(src: impl AsRef<Path>) -> io::Result<Vec<PathBuf>> {
+    let mut files = vec![];
+    collect_files(src.as_ref(), Path::new(""), &mut files)?;
+    files.sort();
+    Ok(files)
+}
+
+fn collect_files(dir: &Path, relative: &Path, files: &mut Vec<PathBuf>) -> io::Result<()> {
+    for entry in fs::read_dir(dir)? {
         let entry = entry?;
-        let ty = entry.file_type()?;
-        let dst_path = dst.as_ref().join(entry.file_name());
-        if ty.is_dir() {
-            copy_dir_all(entry.path(), &dst_path)?;
+        let relative_path = relative.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            collect_files(&entry.path(), &relative_path, files)?;
         } else {
-            println!("->> Copying file: {:?} to {:?}", entry.path(), dst_path);
-            fs::copy(entry.path(), &dst_path)?;
+            files.push(relative_path);
         }
     }
     Ok(())
@@ -0,0 +1,111 @@
+use std::{
+    fs,
+    io::Result,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    dialogue::UserOptions,
+    error::{self, GerminateError},
+    plan::Plan,
+};
+
+pub const JOURNAL_DIR: &str = ".germinate";
+const JOURNAL_FILE: &str = "journal.json";
+
+/// Progress of a build, saved inside the project after every step so an interrupted build can resume
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Journal {
+    pub options: UserOptions,
+    pub steps: Vec<JournalEntry>,
+    #[serde(skip)]
+    path: PathBuf,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JournalEntry {
+    pub step: String,
+    pub done: bool,
+}
+
+impl Journal {
+    pub fn new(options: &UserOptions, root_dir: &Path, plan: &Plan) -> Self {
+        Self {
+            options: options.clone(),
+            steps: plan
+                .steps()
+                .iter()
+                .map(|step| JournalEntry {
+                    step: step.to_string(),
+                    done: false,
+                })
+                .collect(),
+            path: Self::path_for(root_dir),
+        }
+    }
+
+    pub fn path_for(root_dir: &Path) -> PathBuf {
+        root_dir.join(JOURNAL_DIR).join(JOURNAL_FILE)
+    }
+
+    pub fn load(root_dir: &Path) -> error::Result<Self> {
+        let path = Self::path_for(root_dir);
+        let source = format!("journal {}", path.display());
+        let contents = fs::read_to_string(&path)
+            .map_err(GerminateError::io(format!("Failed to read {}", source)))?;
+        let mut journal: Journal =
+            serde_json::from_str(&contents).map_err(|e| GerminateError::input(&source, e))?;
+        journal.path = path;
+        Ok(journal)
+    }
+
+    /// Checks the regenerated plan still lines up with the journal, so completed steps can be trusted
+    pub fn verify(&self, plan: &Plan) -> error::Result<()> {
+        let source = format!("journal {}", self.path.display());
+        let steps = plan.steps();
+        if steps.len() != self.steps.len() {
+            return Err(GerminateError::input(
+                source,
+                "the stack template changed since the build started",
+            ));
+        }
+        for (step, entry) in steps.iter().zip(&self.steps) {
+            if step.to_string() != entry.step {
+                return Err(GerminateError::input(
+                    source,
+                    format!("step `{}` no longer matches the plan", entry.step),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn is_done(&self, index: usize) -> bool {
+        self.steps.get(index).is_some_and(|entry| entry.done)
+    }
+
+    pub fn complete(&mut self, index: usize) -> Result<()> {
+        if let Some(entry) = self.steps.get_mut(index) {
+            entry.done = true;
+        }
+        self.save()
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let dir = self.path.parent().expect("Journal path has no parent");
+        fs::create_dir_all(dir)?;
+        // keeps the journal out of the generated project's initial commit
+        fs::write(dir.join(".gitignore"), "*\n")?;
+        fs::write(&self.path, serde_json::to_string_pretty(self)?)
+    }
+
+    /// Removes the journal folder once the build has finished
+    pub fn remove(self) -> Result<()> {
+        match self.path.parent() {
+            Some(dir) => fs::remove_dir_all(dir),
+            None => Ok(()),
+        }
+    }
+}
@@ -1,38 +1,174 @@
-use std::process::Command;
+use std::{
+    path::{Path, PathBuf},
+    process::Command,
+};
 
-#[derive(Debug, Clone)]
-pub enum Linter {
-    ESLint,
-    Stylelint,
-    Clippy,
+use serde::Deserialize;
+
+use crate::{
+    config::PackageScripts,
+    error::{GerminateError, Result},
+    module::{self, ThenCommands},
+    package_manager::PackageManager,
+    toml_parser,
+};
+
+pub const LINTERS_FILE: &str = "linters.toml";
+/// Config file templates for a linter live in `linters/<name>/` next to `linters.toml`
+const LINTERS_DIR: &str = "linters";
+
+/// Every linter and formatter in the templates directory's `linters.toml`
+#[derive(Debug, Clone, Default)]
+pub struct LinterRegistry {
+    pub linters: Vec<Linter>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Linter {
+    pub name: String,
+    pub install: ThenCommands,
+    /// npm scripts added to the project, e.g. `lint = "eslint ."`
+    pub scripts: PackageScripts,
+    /// Handlebars templates rendered into the project root, if the linter has any
+    pub config_dir: Option<PathBuf>,
+}
+
+/// `linters.toml` as written
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct LintersFile {
+    #[serde(default)]
+    linter: Vec<LinterEntry>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct LinterEntry {
+    name: String,
+    #[serde(default, deserialize_with = "module::then_commands")]
+    install: Option<ThenCommands>,
+    #[serde(default)]
+    scripts: PackageScripts,
+}
+
+impl LinterRegistry {
+    /// Loads `linters.toml` from the templates directory. Without one, stacks can't name any linters
+    pub fn load(templates_dir: &Path) -> Result<Self> {
+        let path = templates_dir.join(LINTERS_FILE);
+        if !path.is_file() {
+            return Ok(Self::default());
+        }
+        let source = toml_parser::read_file(&path, "linter registry")?;
+        let file = toml_parser::deserialize_template::<LintersFile>(&path, &source)?;
+        let linters = file
+            .linter
+            .into_iter()
+            .map(|linter| {
+                let config_dir = templates_dir.join(LINTERS_DIR).join(&linter.name);
+                Linter {
+                    name: linter.name,
+                    install: linter.install.unwrap_or_default(),
+                    scripts: linter.scripts,
+                    config_dir: config_dir.is_dir().then_some(config_dir),
+                }
+            })
+            .collect();
+        Ok(Self { linters })
+    }
+
+    /// Looks up the linters a stack names, in the stack's order
+    pub fn select(&self, names: &[String], stack: &str) -> Result<Vec<Linter>> {
+        names
+            .iter()
+            .map(|name| {
+                self.linters
+                    .iter()
+                    .find(|linter| &linter.name == name)
+                    .cloned()
+                    .ok_or_else(|| {
+                        let available = self
+                            .linters
+                            .iter()
+                            .map(|linter| linter.name.as_str())
+                            .collect::<Vec<_>>();
+                        GerminateError::Config(format!(
+                            "the {} stack uses an unknown linter `{}` (available: {})",
+                            stack,
+                            name,
+                            available.join(", ")
+                        ))
+                    })
+            })
+            .collect()
+    }
 }
 
 impl Linter {
-    pub fn get_install_commands(&self) -> Vec<Command> {
-        match self {
-            Linter::ESLint => {
-                let mut command = Command::new("bun");
-                command.args(&["add", "eslint", "--dev"]);
-                vec![command]
-            }
-            Linter::Stylelint => {
-                let mut command = Command::new("bun");
-                command.args(&["add", "stylelint", "--dev"]);
-                vec![command]
-            }
-            Linter::Clippy => {
-                let mut commands = vec![];
-
-                let mut command = Command::new("rustup");
-                command.arg("update");
-                commands.push(command);
-
-                let mut command = Command::new("rustup");
-                command.args(&["component", "add", "clippy"]);
-                commands.push(command);
-
-                commands
-            }
+    pub fn get_install_commands(&self, manager: PackageManager) -> Vec<Command> {
+        module::generate_then_cmds(&self.install, manager)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn loads_stock_linters() {
+        let registry = LinterRegistry::load(Path::new("templates")).unwrap();
+        let names = ["eslint", "stylelint", "clippy", "rustfmt"].map(String::from);
+        let linters = registry.select(&names, "test").unwrap();
+
+        let eslint = &linters[0];
+        assert_eq!(eslint.scripts["lint"], "eslint .");
+        assert!(eslint
+            .config_dir
+            .as_ref()
+            .is_some_and(|dir| dir.join("eslint.config.js").is_file()));
+        for linter in &linters {
+            assert!(!linter.install.is_empty(), "{} has no install", linter.name);
+            assert!(linter.config_dir.is_some(), "{} has no config", linter.name);
         }
     }
+
+    #[test]
+    fn rejects_unknown_linter() {
+        let templates = TempDir::new().unwrap();
+        fs::write(
+            templates.path().join(LINTERS_FILE),
+            "[[linter]]\nname = \"biome\"\ninstall = [[\"bun\", \"add\", \"@biomejs/biome\", \"--dev\"]]\n",
+        )
+        .unwrap();
+
+        let registry = LinterRegistry::load(templates.path()).unwrap();
+        assert_eq!(registry.linters[0].config_dir, None);
+        let error = registry
+            .select(&["biome".to_string(), "eslint".to_string()], "tsapi")
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Invalid options: the tsapi stack uses an unknown linter `eslint` (available: biome)"
+        );
+    }
+
+    #[test]
+    fn reports_misspelled_linter_keys() {
+        let templates = TempDir::new().unwrap();
+        fs::write(
+            templates.path().join(LINTERS_FILE),
+            "[[linter]]\nname = \"biome\"\nscript = { lint = \"biome lint\" }\n",
+        )
+        .unwrap();
+
+        let error = LinterRegistry::load(templates.path()).unwrap_err();
+        assert!(
+            error
+                .to_string()
+                .ends_with("linters.toml:3:1 at `linter[0].script`: unknown field `script`, expected one of `name`, `install`, `scripts`; did you mean `scripts`?"),
+            "{}",
+            error
+        );
+    }
 }
@@ -1,22 +1,147 @@
+mod answers;
 mod builder;
+mod cli;
 mod config;
 mod container;
-mod db_client;
+mod databases;
 mod dialogue;
+mod error;
 mod file_system;
+mod journal;
 mod linters;
 mod module;
+mod package_manager;
+mod plan;
+mod runner;
+mod stack;
+mod staging;
 mod template_generator;
+mod templates;
 mod toml_parser;
+mod validate;
+mod version;
 
-use crate::{builder::ProjectBuilder, config::ScaffoldConfig};
+use std::path::Path;
+
+use clap::Parser;
+use console::style;
+
+use crate::{
+    builder::ProjectBuilder,
+    cli::{Cli, Commands, NewArgs, ResumeArgs, TemplatesCommand},
+    config::ScaffoldConfig,
+    databases::DatabaseRegistry,
+    error::Result,
+    journal::Journal,
+    plan::BuildReport,
+    runner::RealRunner,
+    staging::StagingDir,
+    templates::{TemplatesDir, TemplatesSource},
+};
 
 fn main() {
-    let user_config = dialogue::get_user_config().unwrap();
-    let app_config = ScaffoldConfig::new(user_config);
-    ProjectBuilder::build(&app_config);
+    if let Err(e) = run(Cli::parse()) {
+        eprintln!("{} {}", style("error:").red().bold(), e);
+        std::process::exit(e.exit_code());
+    }
+}
+
+fn run(cli: Cli) -> Result<()> {
+    // exporting works from the embedded templates, so it doesn't need a templates directory
+    if let Some(Commands::Templates(TemplatesCommand::Export { dir })) = &cli.command {
+        return export_templates(dir);
+    }
+    if let Some(Commands::Schema) = &cli.command {
+        let schema = toml_parser::schema();
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&schema).expect("schemas always serialize")
+        );
+        return Ok(());
+    }
+    // and validating is given the stack folder itself
+    if let Some(Commands::Validate { dir }) = &cli.command {
+        let findings = validate::check(dir);
+        print_warnings(&findings.warnings);
+        findings.into_result(dir)?;
+        println!("{} is a valid stack", dir.display());
+        return Ok(());
+    }
+
+    let templates = TemplatesDir::resolve(cli.templates_dir.as_deref())?;
+    match cli.command {
+        Some(Commands::New(args)) => new_project(args, &templates),
+        Some(Commands::Resume(args)) => resume_project(args, &templates),
+        Some(Commands::Where) => {
+            match templates.source {
+                TemplatesSource::Embedded => println!(
+                    "No templates directory found, using the {} (run `germinate templates export <dir>` to customize them)",
+                    templates.source
+                ),
+                _ => println!("{} (from {})", templates.path.display(), templates.source),
+            }
+            Ok(())
+        }
+        Some(Commands::Templates(_) | Commands::Schema | Commands::Validate { .. }) => {
+            unreachable!("templates, schema and validate commands run before resolving")
+        }
+        None => new_project(Default::default(), &templates),
+    }
+}
+
+fn export_templates(dir: &Path) -> Result<()> {
+    TemplatesDir::export(dir)?;
+    println!("Templates exported to {}", dir.display());
+    println!(
+        "Use them with `--templates-dir {0}` or `GERMINATE_TEMPLATES={0}`",
+        dir.display()
+    );
+    Ok(())
+}
+
+fn new_project(mut args: NewArgs, templates: &TemplatesDir) -> Result<()> {
+    if let Some(path) = args.answers.clone() {
+        let answers = answers::load(&path)?;
+        let registry = DatabaseRegistry::load(&templates.path)?;
+        args = args.with_answers(answers, &registry);
+    }
+    args.check()?;
+
+    let user_config = dialogue::get_user_config(&args, &templates.path)?;
+    let app_config = ScaffoldConfig::new(user_config, &templates.path)?;
+    print_warnings(app_config.stack.template.get_warnings());
+    if args.dry_run {
+        let plan = ProjectBuilder::plan(&app_config)?;
+        println!("Dry run, nothing will be created or executed:");
+        print!("{}", plan);
+        return Ok(());
+    }
+    let report = ProjectBuilder::build(&app_config, &mut RealRunner, args.build.build_options()?)?;
     //?  Can we parallelize it? (future optimization, but keep thinks modularized with a mind towards this end)
 
-    // return success/errors
-    //? Collect success from the scaffold engine and return it to the user
+    finish(report, &app_config)
+}
+
+fn resume_project(args: ResumeArgs, templates: &TemplatesDir) -> Result<()> {
+    let journal = Journal::load(&StagingDir::path_for(&args.dir))?;
+    let mut user_config = journal.options;
+    user_config.output_dir = args.dir.clone();
+
+    let app_config = ScaffoldConfig::new(user_config, &templates.path)?;
+    print_warnings(app_config.stack.template.get_warnings());
+    let report = ProjectBuilder::resume(&app_config, &mut RealRunner, args.build.build_options()?)?;
+
+    finish(report, &app_config)
+}
+
+fn print_warnings(warnings: &[String]) {
+    for warning in warnings {
+        eprintln!("{} {}", style("warning:").yellow().bold(), warning);
+    }
+}
+
+fn finish(report: BuildReport, config: &ScaffoldConfig) -> Result<()> {
+    report.check()?;
+    println!("Project {} created", config.title);
+    Ok(())
 }
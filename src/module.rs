@@ -1,4 +1,23 @@
-use std::process::Command;
+use std::{
+    fs,
+    io::{self, ErrorKind},
+    path::Path,
+    process::Command,
+};
+
+use schemars::{gen::SchemaGenerator, schema::Schema, JsonSchema};
+use semver::Version;
+use serde::{
+    de::{DeserializeOwned, Error},
+    Deserialize, Deserializer,
+};
+
+use crate::{
+    config::TemplateEngine,
+    dialogue::UserOptions,
+    package_manager::PackageManager,
+    version::{self, Cargo, Npm, Requirement, Syntax},
+};
 
 #[derive(Debug, Clone)]
 pub struct Module {
@@ -7,93 +26,762 @@ pub struct Module {
     pub dev: bool,
     pub features: Option<Vec<String>>,
     pub then: Option<ThenCommands>,
+    /// Only installed when the project has this
+    pub when: Option<Condition>,
+    /// How `cargo add` finds and declares the crate, left at the default for npm packages
+    pub cargo: CargoOptions,
 }
 
 pub type ThenCommands = Vec<Vec<String>>;
 
-impl Module {
-    pub fn new(
-        name: String,
-        version: String,
-        dev: bool,
-        then: Option<ThenCommands>,
-        features: Option<Vec<String>>,
-    ) -> Self {
+/// A choice made for the project that a dependency is only needed with, e.g. `when = "spa"`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Condition {
+    /// A single page app in `frontend/`
+    Spa,
+    /// A template engine for server rendered views
+    TemplateEngine,
+    /// Handlebars as the template engine
+    Handlebars,
+    /// Tera as the template engine
+    Tera,
+    /// Askama as the template engine
+    Askama,
+    /// MiniJinja as the template engine
+    Minijinja,
+    /// A database
+    Db,
+    /// Docker containers
+    Containers,
+    /// bun as the package manager, which also runs the project
+    Bun,
+    /// Any other package manager, with Node running the project
+    Node,
+}
+
+impl Condition {
+    pub fn holds(&self, options: &UserOptions) -> bool {
+        match self {
+            Self::Spa => options.spa.is_some(),
+            Self::TemplateEngine => options.template_engine.is_some(),
+            Self::Handlebars => options.template_engine == Some(TemplateEngine::Handlebars),
+            Self::Tera => options.template_engine == Some(TemplateEngine::Tera),
+            Self::Askama => options.template_engine == Some(TemplateEngine::Askama),
+            Self::Minijinja => options.template_engine == Some(TemplateEngine::Minijinja),
+            Self::Db => options.db.is_some(),
+            Self::Containers => options.containers,
+            Self::Bun => options.package_manager == PackageManager::Bun,
+            Self::Node => options.package_manager != PackageManager::Bun,
+        }
+    }
+}
+
+/// The `cargo add` flags that apply to every crate in a call, so only crates that share them are batched
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CargoOptions {
+    pub build: bool,
+    pub target: Option<String>,
+    pub source: CargoSource,
+    /// The dependency's name in `Cargo.toml`, when it differs from the crate's
+    pub rename: Option<String>,
+    pub no_default_features: bool,
+    pub optional: bool,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum CargoSource {
+    #[default]
+    CratesIo,
+    /// A registry configured in `.cargo/config.toml`, by name
+    Registry(String),
+    Git {
+        url: String,
+        reference: Option<GitReference>,
+    },
+    /// A local crate, relative to the project's final folder rather than the staging one
+    Path(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GitReference {
+    Branch(String),
+    Tag(String),
+    Rev(String),
+}
+
+impl CargoOptions {
+    // `cargo add` takes `--rename` and `--path` for a single crate only
+    fn is_alone(&self) -> bool {
+        self.rename.is_some() || matches!(self.source, CargoSource::Path(_))
+    }
+}
+
+/// A stack template dependency entry, before it's turned into a `Module`
+pub trait TemplateDependency: DeserializeOwned + Into<Module> {
+    /// Explains how `version` falls short of `min_version`, if it does
+    fn below_minimum(&self) -> Option<String>;
+}
+
+/// A `[[deps.npm]]` entry of a stack template
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct NpmDependency {
+    /// Package name, as published to npm
+    pub name: String,
+    pub version: Option<Requirement<Npm>>,
+    /// Oldest version the stack works with, warned about when `version` allows older ones
+    #[serde(default, deserialize_with = "version::minimum")]
+    #[schemars(with = "Option<String>")]
+    pub min_version: Option<Version>,
+    /// Install as a dev dependency
+    #[serde(default)]
+    pub dev: bool,
+    /// Unused for npm packages
+    pub features: Option<Vec<String>>,
+    /// Commands to run after the package is installed, each a program followed by its arguments
+    #[serde(default, deserialize_with = "then_commands")]
+    pub then: Option<ThenCommands>,
+    /// Only install the package when the project has this
+    pub when: Option<Condition>,
+}
+
+impl TemplateDependency for NpmDependency {
+    fn below_minimum(&self) -> Option<String> {
+        below_minimum(&self.name, self.version.as_ref(), self.min_version.as_ref())
+    }
+}
+
+impl From<NpmDependency> for Module {
+    fn from(dependency: NpmDependency) -> Self {
         Self {
-            name,
-            version,
-            dev,
-            features,
-            then,
+            name: dependency.name,
+            version: version_text(dependency.version),
+            dev: dependency.dev,
+            features: dependency.features,
+            then: dependency.then,
+            when: dependency.when,
+            cargo: CargoOptions::default(),
         }
     }
 }
 
-pub fn get_npm_cmds(npm_modules: &Vec<Module>) -> Vec<Command> {
-    let mut commands = vec![];
-    for module in npm_modules {
-        let mut command = Command::new("bun");
-        command.arg("add");
+/// A `[[deps.cargo]]` entry of a stack template, with the keys of a `Cargo.toml` dependency
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct CargoEntry {
+    /// Crate name, or the dependency's name in `Cargo.toml` when `package` is given
+    pub name: String,
+    pub version: Option<Requirement<Cargo>>,
+    /// Oldest version the stack works with, warned about when `version` allows older ones
+    #[serde(default, deserialize_with = "version::minimum")]
+    #[schemars(with = "Option<String>")]
+    pub min_version: Option<Version>,
+    /// Install as a dev dependency
+    #[serde(default)]
+    pub dev: bool,
+    /// Install as a build dependency
+    #[serde(default)]
+    pub build: bool,
+    /// Install for a target only, e.g. `cfg(unix)` or `x86_64-pc-windows-msvc`
+    pub target: Option<String>,
+    /// Features to enable
+    pub features: Option<Vec<String>>,
+    /// Set to false to leave the crate's default features off
+    #[serde(rename = "default-features", default = "enabled")]
+    pub default_features: bool,
+    /// Only build the crate when a feature of the project enables it
+    #[serde(default)]
+    pub optional: bool,
+    /// Git repository to install from instead of a registry
+    pub git: Option<String>,
+    /// Git branch to use, with `git`
+    pub branch: Option<String>,
+    /// Git tag to use, with `git`
+    pub tag: Option<String>,
+    /// Git commit to use, with `git`
+    pub rev: Option<String>,
+    /// Local crate to install, relative to the project's final folder
+    pub path: Option<String>,
+    /// Name to give the dependency in `Cargo.toml`, when `name` is the crate's
+    pub rename: Option<String>,
+    /// Crate to install, when `name` is the dependency's name in `Cargo.toml`
+    pub package: Option<String>,
+    /// Registry to install from instead of crates.io
+    pub registry: Option<String>,
+    /// Commands to run after the crate is installed, each a program followed by its arguments
+    #[serde(default, deserialize_with = "then_commands")]
+    pub then: Option<ThenCommands>,
+    /// Only install the crate when the project has this
+    pub when: Option<Condition>,
+}
 
-        if module.version != "latest" {
-            command.arg(format!("{}@{}", module.name, module.version));
+fn enabled() -> bool {
+    true
+}
+
+/// A `[[deps.cargo]]` entry whose keys have been checked against each other
+#[derive(Debug)]
+pub struct CargoDependency(CargoEntry);
+
+impl<'de> Deserialize<'de> for CargoDependency {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let entry = CargoEntry::deserialize(deserializer)?;
+        entry.check().map_err(D::Error::custom)?;
+        Ok(Self(entry))
+    }
+}
+
+impl JsonSchema for CargoDependency {
+    fn schema_name() -> String {
+        "CargoDependency".to_string()
+    }
+
+    fn json_schema(gen: &mut SchemaGenerator) -> Schema {
+        CargoEntry::json_schema(gen)
+    }
+}
+
+impl CargoEntry {
+    // the combinations `cargo add` would reject, or that can't mean anything
+    fn check(&self) -> Result<(), String> {
+        let git_refs = [
+            ("branch", &self.branch),
+            ("tag", &self.tag),
+            ("rev", &self.rev),
+        ]
+        .into_iter()
+        .filter(|(_, value)| value.is_some())
+        .map(|(key, _)| key)
+        .collect::<Vec<_>>();
+        let problem = if self.dev && self.build {
+            "a dependency can't be both `dev` and `build`".to_string()
+        } else if self.dev && self.optional {
+            "dev dependencies can't be `optional`".to_string()
+        } else if self.git.is_none() && !git_refs.is_empty() {
+            format!("`{}` needs a `git` repository", git_refs[0])
+        } else if git_refs.len() > 1 {
+            format!(
+                "`{}` pick the same commit, give only one",
+                git_refs.join("` and `")
+            )
+        } else if self.git.is_some() && self.path.is_some() {
+            "`git` and `path` are different sources, give only one".to_string()
+        } else if self.git.is_some() && self.registry.is_some() {
+            "`git` and `registry` are different sources, give only one".to_string()
+        } else if self.git.is_some() && self.version.is_some() {
+            "`cargo add` can't pin a `version` from `git`, use `tag` or `rev`".to_string()
+        } else if self.rename.is_some() && self.package.is_some() {
+            "`rename` and `package` both rename the dependency, give only one".to_string()
         } else {
-            command.arg(&module.name);
-        }
+            return Ok(());
+        };
+        Err(format!("{}: {}", self.name, problem))
+    }
+}
 
-        if module.dev {
-            command.arg("--dev");
+impl TemplateDependency for CargoDependency {
+    fn below_minimum(&self) -> Option<String> {
+        let entry = &self.0;
+        below_minimum(
+            &entry.name,
+            entry.version.as_ref(),
+            entry.min_version.as_ref(),
+        )
+    }
+}
+
+impl From<CargoDependency> for Module {
+    fn from(CargoDependency(entry): CargoDependency) -> Self {
+        // `cargo add` is given the crate, and renames it to the dependency's name
+        let (name, rename) = match entry.package {
+            Some(package) => (package, Some(entry.name)),
+            None => (entry.name, entry.rename),
+        };
+        let source = match (entry.git, entry.path, entry.registry) {
+            (Some(url), _, _) => CargoSource::Git {
+                url,
+                reference: entry
+                    .branch
+                    .map(GitReference::Branch)
+                    .or(entry.tag.map(GitReference::Tag))
+                    .or(entry.rev.map(GitReference::Rev)),
+            },
+            (None, Some(path), _) => CargoSource::Path(path),
+            (None, None, Some(registry)) => CargoSource::Registry(registry),
+            (None, None, None) => CargoSource::CratesIo,
+        };
+
+        Self {
+            name,
+            version: version_text(entry.version),
+            dev: entry.dev,
+            features: entry.features,
+            then: entry.then,
+            when: entry.when,
+            cargo: CargoOptions {
+                build: entry.build,
+                target: entry.target,
+                source,
+                rename,
+                no_default_features: !entry.default_features,
+                optional: entry.optional,
+            },
         }
+    }
+}
 
-        commands.push(command);
+fn version_text<S>(version: Option<Requirement<S>>) -> String {
+    version.map_or_else(|| "latest".to_string(), |version| version.text)
+}
+
+fn below_minimum<S: Syntax>(
+    name: &str,
+    version: Option<&Requirement<S>>,
+    minimum: Option<&Version>,
+) -> Option<String> {
+    let (version, minimum) = (version?, minimum?);
+    version.allows_below(minimum).then(|| {
+        format!(
+            "`{}` allows versions of {} older than its min_version {}",
+            version.text, name, minimum
+        )
+    })
+}
+
+pub(crate) fn then_commands<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<ThenCommands>, D::Error> {
+    let commands = ThenCommands::deserialize(deserializer)?;
+    match commands.iter().any(Vec::is_empty) {
+        true => Err(D::Error::custom("empty command")),
+        false => Ok(Some(commands)),
+    }
+}
 
-        if let Some(then_commands) = &module.then {
-            commands.append(&mut generate_then_cmds(&then_commands));
+impl Module {
+    /// The name as passed to `cargo add` or `bun add`, pinned when a version is given
+    fn spec(&self) -> String {
+        if self.version != "latest" {
+            format!("{}@{}", self.name, self.version)
+        } else {
+            self.name.clone()
         }
     }
+}
+
+/// Installs the modules with one package manager call for regular and one for dev dependencies,
+/// each followed by the `then` commands of its modules
+pub fn get_npm_cmds(npm_modules: &[Module], manager: PackageManager) -> Vec<Command> {
+    let mut commands = vec![];
+    for batch in batches(npm_modules) {
+        let dev = batch[0].dev;
+        commands.push(manager.add(batch.iter().map(|module| module.spec()), dev));
+        commands.append(&mut batch_then_cmds(&batch, manager));
+    }
 
     commands
 }
 
-pub fn get_cargo_cmds(cargo_modules: &Vec<Module>) -> Vec<Command> {
+/// Installs the modules with one `cargo add` for each set of crates that share their kind,
+/// target and source, each followed by the `then` commands of its modules. `path` crates are
+/// found from `project_dir`, the folder the project ends up in
+pub fn get_cargo_cmds(
+    cargo_modules: &[Module],
+    manager: PackageManager,
+    project_dir: &Path,
+) -> Vec<Command> {
     let mut commands = vec![];
-    for module in cargo_modules {
+    for batch in batches(cargo_modules) {
         let mut command = Command::new("cargo");
         command.env("CARGO_NET_GIT_FETCH_WITH_CLI", "true");
         command.arg("add");
+        command.args(batch.iter().map(|module| module.spec()));
 
-        if module.version != "latest" {
-            command.arg(format!("{}@{}", module.name, module.version));
-        } else {
-            command.arg(&module.name);
-        }
-
-        if module.dev {
+        let (dev, options) = (batch[0].dev, &batch[0].cargo);
+        if dev {
             command.arg("--dev");
         }
+        if options.build {
+            command.arg("--build");
+        }
+        if let Some(target) = &options.target {
+            command.args(["--target", target]);
+        }
+        match &options.source {
+            CargoSource::CratesIo => {}
+            CargoSource::Registry(registry) => {
+                command.args(["--registry", registry]);
+            }
+            CargoSource::Git { url, reference } => {
+                command.args(["--git", url]);
+                match reference {
+                    Some(GitReference::Branch(branch)) => command.args(["--branch", branch]),
+                    Some(GitReference::Tag(tag)) => command.args(["--tag", tag]),
+                    Some(GitReference::Rev(rev)) => command.args(["--rev", rev]),
+                    None => &mut command,
+                };
+            }
+            CargoSource::Path(path) => {
+                command.arg("--path").arg(project_dir.join(path));
+            }
+        }
+        if let Some(rename) = &options.rename {
+            command.args(["--rename", rename]);
+        }
+        if options.no_default_features {
+            command.arg("--no-default-features");
+        }
+        if options.optional {
+            command.arg("--optional");
+        }
 
-        if let Some(features) = &module.features {
+        // with several crates in one call, cargo needs each feature as `crate/feature`
+        let features = batch
+            .iter()
+            .flat_map(|module| {
+                module
+                    .features
+                    .iter()
+                    .flatten()
+                    .map(|feature| match batch.len() {
+                        1 => feature.to_string(),
+                        _ => format!("{}/{}", module.name, feature),
+                    })
+            })
+            .collect::<Vec<_>>();
+        if !features.is_empty() {
             command.arg("--features");
             command.arg(features.join(","));
         }
 
         commands.push(command);
+        commands.append(&mut batch_then_cmds(&batch, manager));
+    }
+    commands
+}
+
+/// Points the `path` crates in the project's `Cargo.toml` back at the paths the template gave.
+/// `cargo add` writes them relative to the staging folder it ran in, which sits a level deeper
+/// than the project once it's moved into place
+pub fn pin_cargo_paths(project_dir: &Path, cargo_modules: &[Module]) -> io::Result<()> {
+    let pinned = cargo_modules
+        .iter()
+        .filter_map(|module| match &module.cargo.source {
+            CargoSource::Path(path) => Some((module, path)),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    if pinned.is_empty() {
+        return Ok(());
+    }
 
-        if let Some(then_commands) = &module.then {
-            commands.append(&mut generate_then_cmds(then_commands))
+    let manifest = project_dir.join("Cargo.toml");
+    let mut document = fs::read_to_string(&manifest)?
+        .parse::<toml_edit::Document>()
+        .map_err(|e| io::Error::new(ErrorKind::InvalidData, e))?;
+    for (module, path) in pinned {
+        let key = module.cargo.rename.as_deref().unwrap_or(&module.name);
+        let table = match (module.dev, module.cargo.build) {
+            (true, _) => "dev-dependencies",
+            (_, true) => "build-dependencies",
+            _ => "dependencies",
+        };
+        let dependencies = match &module.cargo.target {
+            Some(target) => document
+                .get_mut("target")
+                .and_then(|targets| targets.get_mut(target)),
+            None => Some(document.as_item_mut()),
+        };
+        let entry = dependencies
+            .and_then(|dependencies| dependencies.get_mut(table))
+            .and_then(|table| table.get_mut(key))
+            .and_then(|entry| entry.get_mut("path"));
+        match entry {
+            Some(entry) => *entry = toml_edit::value(path.as_str()),
+            None => {
+                return Err(io::Error::new(
+                    ErrorKind::NotFound,
+                    format!("no `path` for `{}` in {}", key, manifest.display()),
+                ))
+            }
         }
     }
-    commands
+    fs::write(&manifest, document.to_string())
 }
 
-fn generate_then_cmds(then_commands: &ThenCommands) -> Vec<Command> {
-    let mut commands = vec![];
-    for cmd in then_commands {
-        let mut command = Command::new(&cmd[0]);
-        for arg in &cmd[1..] {
-            command.arg(arg);
+// modules grouped by the flags their install call takes, in their original order within a
+// group: regular dependencies first, then dev and build dependencies
+fn batches(modules: &[Module]) -> Vec<Vec<&Module>> {
+    let mut batches: Vec<Vec<&Module>> = vec![];
+    for module in modules {
+        let shared = batches.iter_mut().find(|batch| {
+            let first = batch[0];
+            first.dev == module.dev && first.cargo == module.cargo && !module.cargo.is_alone()
+        });
+        match shared {
+            Some(batch) => batch.push(module),
+            None => batches.push(vec![module]),
         }
-        commands.push(command);
     }
-    commands
+    batches.sort_by_key(|batch| (batch[0].dev, batch[0].cargo.build));
+    batches
+}
+
+fn batch_then_cmds(batch: &[&Module], manager: PackageManager) -> Vec<Command> {
+    batch
+        .iter()
+        .filter_map(|module| module.then.as_ref())
+        .flat_map(|then| generate_then_cmds(then, manager))
+        .collect()
+}
+
+/// Commands from a template, with any written for bun rewritten for the project's package manager
+pub fn generate_then_cmds(then_commands: &ThenCommands, manager: PackageManager) -> Vec<Command> {
+    then_commands
+        .iter()
+        .map(|cmd| manager.command(cmd))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::package_manager;
+
+    fn module(name: &str, version: &str, dev: bool, features: &[&str]) -> Module {
+        let features = (!features.is_empty())
+            .then(|| features.iter().map(|feature| feature.to_string()).collect());
+        Module {
+            name: name.to_string(),
+            version: version.to_string(),
+            dev,
+            features,
+            then: None,
+            when: None,
+            cargo: CargoOptions::default(),
+        }
+    }
+
+    fn cargo_modules(source: &str) -> Result<Vec<Module>, toml::de::Error> {
+        #[derive(Deserialize)]
+        struct Deps {
+            cargo: Vec<CargoDependency>,
+        }
+        let deps = toml::from_str::<Deps>(source)?;
+        Ok(deps.cargo.into_iter().map(Module::from).collect())
+    }
+
+    fn lines(commands: &[Command]) -> Vec<String> {
+        commands.iter().map(package_manager::line).collect()
+    }
+
+    #[test]
+    fn batches_cargo_modules() {
+        let mut sqlx = module("sqlx", "0.7", false, &["postgres", "runtime-tokio"]);
+        sqlx.then = Some(vec![vec![
+            "cargo".to_string(),
+            "install".to_string(),
+            "sqlx-cli".to_string(),
+        ]]);
+        let modules = [
+            module("mockall", "latest", true, &[]),
+            module("clap", "latest", false, &["derive"]),
+            sqlx,
+            module("serde", "latest", false, &[]),
+        ];
+
+        assert_eq!(
+            lines(&get_cargo_cmds(&modules, PackageManager::Bun, Path::new("app"))),
+            [
+                "cargo add clap sqlx@0.7 serde --features clap/derive,sqlx/postgres,sqlx/runtime-tokio",
+                "cargo install sqlx-cli",
+                "cargo add mockall --dev",
+            ]
+        );
+    }
+
+    #[test]
+    fn translates_cargo_specs() {
+        let modules = cargo_modules(
+            r#"
+            [[cargo]]
+            name = "serde"
+            features = ["derive"]
+
+            [[cargo]]
+            name = "tokio"
+            default-features = false
+            features = ["rt"]
+
+            [[cargo]]
+            name = "clap"
+            default-features = false
+
+            [[cargo]]
+            name = "json"
+            package = "serde_json"
+
+            [[cargo]]
+            name = "internal"
+            git = "https://example.com/internal.git"
+            tag = "v2"
+            optional = true
+
+            [[cargo]]
+            name = "shared"
+            path = "../shared"
+
+            [[cargo]]
+            name = "nix"
+            target = "cfg(unix)"
+
+            [[cargo]]
+            name = "cc"
+            build = true
+
+            [[cargo]]
+            name = "mockall"
+            dev = true
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            lines(&get_cargo_cmds(
+                &modules,
+                PackageManager::Bun,
+                Path::new("app")
+            )),
+            [
+                "cargo add serde --features derive",
+                "cargo add tokio clap --no-default-features --features tokio/rt",
+                "cargo add serde_json --rename json",
+                "cargo add internal --git https://example.com/internal.git --tag v2 --optional",
+                "cargo add shared --path app/../shared",
+                "cargo add nix --target cfg(unix)",
+                "cargo add cc --build",
+                "cargo add mockall --dev",
+            ]
+        );
+    }
+
+    #[test]
+    fn resolves_path_crates_from_the_project() {
+        let cargo = |dir: &Path, args: &[&str]| {
+            let output = Command::new("cargo")
+                .args(args)
+                .current_dir(dir)
+                .output()
+                .unwrap();
+            assert!(
+                output.status.success(),
+                "cargo {:?}: {}",
+                args,
+                String::from_utf8_lossy(&output.stderr)
+            );
+        };
+        let parent = tempfile::TempDir::new().unwrap();
+        cargo(
+            parent.path(),
+            &["new", "--lib", "--offline", "--quiet", "shared"],
+        );
+        let staging = parent.path().join(".germinate-staging/app");
+        fs::create_dir_all(&staging).unwrap();
+        cargo(&staging, &["init", "--name", "app", "--offline", "--quiet"]);
+
+        let modules = cargo_modules(
+            r#"
+            [[cargo]]
+            name = "shared"
+            path = "../shared"
+            "#,
+        )
+        .unwrap();
+        let project = parent.path().join("app");
+        for mut command in get_cargo_cmds(&modules, PackageManager::Bun, &project) {
+            let output = command
+                .current_dir(&staging)
+                .arg("--offline")
+                .output()
+                .unwrap();
+            assert!(
+                output.status.success(),
+                "{}",
+                String::from_utf8_lossy(&output.stderr)
+            );
+        }
+        fs::rename(&staging, &project).unwrap();
+        pin_cargo_paths(&project, &modules).unwrap();
+
+        let manifest = fs::read_to_string(project.join("Cargo.toml")).unwrap();
+        assert!(manifest.contains(r#"path = "../shared""#), "{}", manifest);
+        cargo(
+            &project,
+            &["metadata", "--offline", "--format-version", "1"],
+        );
+    }
+
+    #[test]
+    fn rejects_conflicting_cargo_keys() {
+        let cases = [
+            (
+                "name = \"a\"\ndev = true\nbuild = true",
+                "a: a dependency can't be both `dev` and `build`",
+            ),
+            (
+                "name = \"a\"\ntag = \"v1\"",
+                "a: `tag` needs a `git` repository",
+            ),
+            (
+                "name = \"a\"\ngit = \"https://example.com/a.git\"\nbranch = \"main\"\nrev = \"abc123\"",
+                "a: `branch` and `rev` pick the same commit, give only one",
+            ),
+            (
+                "name = \"a\"\ngit = \"https://example.com/a.git\"\nversion = \"1.0\"",
+                "a: `cargo add` can't pin a `version` from `git`, use `tag` or `rev`",
+            ),
+            (
+                "name = \"a\"\nrename = \"b\"\npackage = \"c\"",
+                "a: `rename` and `package` both rename the dependency, give only one",
+            ),
+        ];
+        for (entry, message) in cases {
+            let error = cargo_modules(&format!("[[cargo]]\n{}", entry)).unwrap_err();
+            assert_eq!(error.message(), message);
+        }
+    }
+
+    #[test]
+    fn batches_npm_modules() {
+        let mut prisma = module("prisma", "latest", true, &[]);
+        prisma.then = Some(vec![vec![
+            "bunx".to_string(),
+            "prisma".to_string(),
+            "init".to_string(),
+        ]]);
+        let modules = [
+            module("hono", "latest", false, &[]),
+            prisma,
+            module("zod", "3", false, &[]),
+        ];
+
+        assert_eq!(
+            lines(&get_npm_cmds(&modules, PackageManager::Bun)),
+            [
+                "bun add hono zod@3",
+                "bun add prisma --dev",
+                "bunx prisma init"
+            ]
+        );
+        assert_eq!(
+            lines(&get_npm_cmds(&modules, PackageManager::Npm)),
+            [
+                "npm install hono zod@3",
+                "npm install prisma --save-dev",
+                "npx --yes prisma init"
+            ]
+        );
+        assert!(get_npm_cmds(&[], PackageManager::Bun).is_empty());
+    }
 }
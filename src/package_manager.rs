@@ -0,0 +1,351 @@
+use std::{ffi::OsStr, path::Path, process::Command};
+
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use strum::{Display, EnumString, EnumVariantNames};
+
+/// The JavaScript package manager that every `package.json` command germinate runs goes through
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    Default,
+    PartialEq,
+    Display,
+    EnumString,
+    EnumVariantNames,
+    ValueEnum,
+    Serialize,
+    Deserialize,
+)]
+#[serde(rename_all = "lowercase")]
+#[strum(serialize_all = "lowercase")]
+pub enum PackageManager {
+    #[default]
+    Bun,
+    Npm,
+    Pnpm,
+    /// Yarn classic (1.x), the version node images ship and corepack picks without a `packageManager`
+    /// field. Where classic has no command of its own (`dlx`, `pkg set`), the npm bundled with node is used
+    Yarn,
+}
+
+impl PackageManager {
+    fn program(&self) -> &'static str {
+        match self {
+            Self::Bun => "bun",
+            Self::Npm => "npm",
+            Self::Pnpm => "pnpm",
+            Self::Yarn => "yarn",
+        }
+    }
+
+    /// Creates a `package.json` without prompting
+    pub fn init(&self) -> Command {
+        let mut command = Command::new(self.program());
+        match self {
+            Self::Pnpm => command.arg("init"),
+            _ => command.args(["init", "-y"]),
+        };
+        command
+    }
+
+    /// Installs the packages in one call, as dev dependencies when `dev` is set
+    pub fn add<I, S>(&self, packages: I, dev: bool) -> Command
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<OsStr>,
+    {
+        let mut command = Command::new(self.program());
+        match self {
+            Self::Npm => command.arg("install"),
+            _ => command.arg("add"),
+        };
+        command.args(packages);
+        if dev {
+            command.arg(match self {
+                Self::Bun | Self::Yarn => "--dev",
+                Self::Npm | Self::Pnpm => "--save-dev",
+            });
+        }
+        command
+    }
+
+    /// Runs a package's binary, downloading it first if it isn't installed (`bunx`, `npx`, `pnpm dlx`)
+    pub fn exec<I, S>(&self, args: I) -> Command
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<OsStr>,
+    {
+        let mut command = match self {
+            Self::Bun => Command::new("bunx"),
+            // without --yes, npx asks before installing a package it hasn't seen
+            Self::Npm | Self::Yarn => {
+                let mut command = Command::new("npx");
+                command.arg("--yes");
+                command
+            }
+            Self::Pnpm => {
+                let mut command = Command::new(self.program());
+                command.arg("dlx");
+                command
+            }
+        };
+        command.args(args);
+        command
+    }
+
+    /// Runs a script from `package.json`, passing it any further arguments
+    pub fn run<I, S>(&self, args: I) -> Command
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<OsStr>,
+    {
+        let mut command = Command::new(self.program());
+        command.arg("run");
+        let mut args = args.into_iter();
+        command.args(args.next());
+        // npm keeps arguments before `--` for itself
+        match (self, args.next()) {
+            (Self::Npm, Some(arg)) => command.arg("--").arg(arg).args(args),
+            (_, arg) => command.args(arg).args(args),
+        };
+        command
+    }
+
+    /// Installs exactly the versions in the lockfile, failing when it's out of date
+    pub fn install_frozen(&self) -> Command {
+        let mut command = Command::new(self.program());
+        match self {
+            Self::Npm => command.arg("ci"),
+            _ => command.args(["install", "--frozen-lockfile"]),
+        };
+        command
+    }
+
+    /// The lockfile `install` writes, as a Dockerfile `COPY` source. bun has written `bun.lock`
+    /// since 1.2 and `bun.lockb` before, so the pattern takes either
+    pub fn lockfile(&self) -> &'static str {
+        match self {
+            Self::Bun => "bun.lock*",
+            Self::Npm => "package-lock.json",
+            Self::Pnpm => "pnpm-lock.yaml",
+            Self::Yarn => "yarn.lock",
+        }
+    }
+
+    /// Rewrites a `package.json` script written for bun. The other package managers run scripts
+    /// with Node, which can't run TypeScript itself, so `bun run <file>` becomes `tsx <file>` and
+    /// `bun run --hot <file>` becomes `tsx watch <file>`. Scripts that don't start with bun are kept
+    pub fn script(&self, script: &str) -> String {
+        if *self == Self::Bun {
+            return script.to_string();
+        }
+        let words = script.split_whitespace().collect::<Vec<_>>();
+        let is_file = |word: &str| Path::new(word).extension().is_some();
+        match words.as_slice() {
+            ["bun", "run", "--hot", file, rest @ ..] if is_file(file) => {
+                [&["tsx", "watch", file], rest].concat().join(" ")
+            }
+            ["bun", "run", file, rest @ ..] if is_file(file) => {
+                [&["tsx", file], rest].concat().join(" ")
+            }
+            ["bun" | "bunx", ..] => {
+                let args = words
+                    .iter()
+                    .map(|word| word.to_string())
+                    .collect::<Vec<_>>();
+                line(&self.command(&args))
+            }
+            _ => script.to_string(),
+        }
+    }
+
+    /// Sets a `package.json` field, e.g. `scripts.lint`
+    pub fn pkg_set(&self, key: &str, value: &str) -> Command {
+        let mut command = match self {
+            Self::Bun => {
+                let mut command = Command::new("bun");
+                command.arg("pm");
+                command
+            }
+            Self::Npm | Self::Pnpm => Command::new(self.program()),
+            Self::Yarn => Command::new("npm"),
+        };
+        command
+            .args(["pkg", "set"])
+            .arg(format!("{}={}", key, value));
+        command
+    }
+
+    /// Builds a command from a template, where JavaScript commands are written for bun
+    /// (`bun add`, `bun run`, `bunx`) and rewritten for this package manager. Anything else runs as is
+    pub fn command(&self, args: &[String]) -> Command {
+        match args {
+            [program, rest @ ..] if program == "bunx" => self.exec(rest),
+            [program, subcommand, rest @ ..] if program == "bun" && subcommand == "x" => {
+                self.exec(rest)
+            }
+            [program, subcommand, rest @ ..] if program == "bun" && subcommand == "run" => {
+                self.run(rest)
+            }
+            [program, subcommand, rest @ ..] if program == "bun" && subcommand == "add" => {
+                let dev = rest
+                    .iter()
+                    .any(|arg| matches!(arg.as_str(), "--dev" | "-d" | "-D"));
+                let packages = rest
+                    .iter()
+                    .filter(|arg| !matches!(arg.as_str(), "--dev" | "-d" | "-D"));
+                self.add(packages, dev)
+            }
+            [program, rest @ ..] if program == "bun" => {
+                let mut command = Command::new(self.program());
+                command.args(rest);
+                command
+            }
+            [program, rest @ ..] => {
+                let mut command = Command::new(program);
+                command.args(rest);
+                command
+            }
+            [] => unreachable!("template commands are checked for being empty when parsed"),
+        }
+    }
+}
+
+/// A command as it would be typed in a shell, for scripts and Dockerfiles. Arguments are
+/// expected not to need quoting
+pub fn line(command: &Command) -> String {
+    std::iter::once(command.get_program())
+        .chain(command.get_args())
+        .map(|arg| arg.to_string_lossy().to_string())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn template(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn builds_commands_for_each_manager() {
+        let expected = [
+            (
+                PackageManager::Bun,
+                [
+                    "bun init -y",
+                    "bun add zod --dev",
+                    "bunx prisma init",
+                    "bun pm pkg set scripts.lint=eslint .",
+                ],
+            ),
+            (
+                PackageManager::Npm,
+                [
+                    "npm init -y",
+                    "npm install zod --save-dev",
+                    "npx --yes prisma init",
+                    "npm pkg set scripts.lint=eslint .",
+                ],
+            ),
+            (
+                PackageManager::Pnpm,
+                [
+                    "pnpm init",
+                    "pnpm add zod --save-dev",
+                    "pnpm dlx prisma init",
+                    "pnpm pkg set scripts.lint=eslint .",
+                ],
+            ),
+            (
+                PackageManager::Yarn,
+                [
+                    "yarn init -y",
+                    "yarn add zod --dev",
+                    "npx --yes prisma init",
+                    "npm pkg set scripts.lint=eslint .",
+                ],
+            ),
+        ];
+        for (manager, lines) in expected {
+            let commands = [
+                manager.init(),
+                manager.add(["zod"], true),
+                manager.exec(["prisma", "init"]),
+                manager.pkg_set("scripts.lint", "eslint ."),
+            ];
+            assert_eq!(commands.iter().map(line).collect::<Vec<_>>(), lines);
+        }
+    }
+
+    #[test]
+    fn rewrites_bun_template_commands() {
+        let pnpm = PackageManager::Pnpm;
+        let rewritten = |args: &[&str]| line(&pnpm.command(&template(args)));
+        assert_eq!(
+            rewritten(&["bunx", "prisma", "init"]),
+            "pnpm dlx prisma init"
+        );
+        assert_eq!(
+            rewritten(&["bun", "add", "eslint", "--dev"]),
+            "pnpm add eslint --save-dev"
+        );
+        assert_eq!(rewritten(&["bun", "run", "build"]), "pnpm run build");
+        assert_eq!(
+            line(&PackageManager::Npm.command(&template(&["bun", "run", "lint", "--fix"]))),
+            "npm run lint -- --fix"
+        );
+        assert_eq!(rewritten(&["bun", "install"]), "pnpm install");
+        assert_eq!(rewritten(&["rustup", "update"]), "rustup update");
+    }
+
+    #[test]
+    fn rewrites_bun_scripts_for_node() {
+        let npm = PackageManager::Npm;
+        assert_eq!(npm.script("bun run src/index.ts"), "tsx src/index.ts");
+        assert_eq!(
+            npm.script("bun run --hot src/index.ts"),
+            "tsx watch src/index.ts"
+        );
+        assert_eq!(
+            npm.script("bun run build --watch"),
+            "npm run build -- --watch"
+        );
+        assert_eq!(npm.script("bunx vite build"), "npx --yes vite build");
+        assert_eq!(npm.script("eslint ."), "eslint .");
+        assert_eq!(
+            PackageManager::Bun.script("bun run --hot src/index.ts"),
+            "bun run --hot src/index.ts"
+        );
+    }
+
+    #[test]
+    fn installs_from_lockfile() {
+        let frozen = [
+            (
+                PackageManager::Bun,
+                "bun.lock*",
+                "bun install --frozen-lockfile",
+            ),
+            (PackageManager::Npm, "package-lock.json", "npm ci"),
+            (
+                PackageManager::Pnpm,
+                "pnpm-lock.yaml",
+                "pnpm install --frozen-lockfile",
+            ),
+            (
+                PackageManager::Yarn,
+                "yarn.lock",
+                "yarn install --frozen-lockfile",
+            ),
+        ];
+        for (manager, lockfile, install) in frozen {
+            assert_eq!(manager.lockfile(), lockfile);
+            assert_eq!(line(&manager.install_frozen()), install);
+        }
+    }
+}
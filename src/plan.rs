@@ -0,0 +1,291 @@
+use std::{
+    fmt, fs,
+    io::{self, ErrorKind},
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use serde_json::Value;
+
+use crate::{
+    error::{self, GerminateError},
+    journal::Journal,
+    runner::{CommandOutput, CommandRunner},
+    template_generator,
+};
+
+/// A single filesystem operation or external command queued by the project builder
+#[derive(Debug)]
+pub enum Step {
+    CreateDir(PathBuf),
+    WriteFile {
+        path: PathBuf,
+        contents: String,
+    },
+    CopyFile {
+        src: PathBuf,
+        dest: PathBuf,
+    },
+    RenderFile {
+        src: PathBuf,
+        dest: PathBuf,
+        data: Value,
+    },
+    RemoveFile(PathBuf),
+    Run(Command),
+}
+
+/// What to do when a step fails
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FailurePolicy {
+    /// Stop at the first failed step
+    Abort,
+    /// Record the failure and keep going
+    Continue,
+    /// Retry a failed step up to N more times, then stop
+    Retry(u32),
+}
+
+/// The outcome of a single executed step
+#[derive(Debug)]
+pub struct StepResult {
+    pub index: usize,
+    pub step: String,
+    pub attempts: u32,
+    pub error: Option<String>,
+}
+
+impl StepResult {
+    pub fn succeeded(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
+impl fmt::Display for StepResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "step {} ({})", self.index + 1, self.step)?;
+        if let Some(error) = &self.error {
+            write!(f, " failed after {} attempt(s): {}", self.attempts, error)?;
+        }
+        Ok(())
+    }
+}
+
+/// Results for every step that ran, in order
+#[derive(Debug, Default)]
+pub struct BuildReport {
+    pub results: Vec<StepResult>,
+}
+
+impl BuildReport {
+    pub fn failures(&self) -> impl Iterator<Item = &StepResult> {
+        self.results.iter().filter(|result| !result.succeeded())
+    }
+
+    /// Turns any failed steps into a build error naming each of them
+    pub fn check(&self) -> error::Result<()> {
+        let failures: Vec<String> = self.failures().map(|f| f.to_string()).collect();
+        match failures.is_empty() {
+            true => Ok(()),
+            false => Err(GerminateError::Build { failures }),
+        }
+    }
+}
+
+/// The ordered list of steps needed to scaffold a project. Nothing touches the disk until `execute`
+#[derive(Debug)]
+pub struct Plan {
+    root_dir: PathBuf,
+    steps: Vec<Step>,
+}
+
+impl Plan {
+    pub fn new(root_dir: &Path) -> Self {
+        Self {
+            root_dir: root_dir.to_path_buf(),
+            steps: vec![],
+        }
+    }
+
+    pub fn push(&mut self, step: Step) {
+        self.steps.push(step);
+    }
+
+    /// Queues a command to run from the project root
+    pub fn run(&mut self, mut command: Command) {
+        command.current_dir(&self.root_dir);
+        self.push(Step::Run(command));
+    }
+
+    pub fn run_all(&mut self, commands: Vec<Command>) {
+        for command in commands {
+            self.run(command);
+        }
+    }
+
+    pub fn steps(&self) -> &[Step] {
+        &self.steps
+    }
+
+    /// Runs every step the journal hasn't marked done, recording progress as it goes
+    pub fn execute(
+        self,
+        runner: &mut dyn CommandRunner,
+        policy: FailurePolicy,
+        journal: &mut Journal,
+    ) -> BuildReport {
+        let mut report = BuildReport::default();
+        let max_attempts = match policy {
+            FailurePolicy::Retry(retries) => retries + 1,
+            _ => 1,
+        };
+
+        for (index, mut step) in self.steps.into_iter().enumerate() {
+            if journal.is_done(index) {
+                println!("->> Skipping completed step: {}", step);
+                continue;
+            }
+            println!("->> {}", step);
+            let mut attempts = 0;
+            let error = loop {
+                attempts += 1;
+                match step.execute(runner) {
+                    Ok(()) => {
+                        break journal
+                            .complete(index)
+                            .err()
+                            .map(|e| format!("Failed to update journal: {}", e))
+                    }
+                    Err(e) if attempts < max_attempts => {
+                        println!(
+                            "->> Step failed, retrying ({}/{}): {}",
+                            attempts, max_attempts, e
+                        );
+                    }
+                    Err(e) => break Some(e),
+                }
+            };
+
+            let failed = error.is_some();
+            report.results.push(StepResult {
+                index,
+                step: step.to_string(),
+                attempts,
+                error,
+            });
+            if failed && policy != FailurePolicy::Continue {
+                break;
+            }
+        }
+        report
+    }
+}
+
+impl Step {
+    fn execute(&mut self, runner: &mut dyn CommandRunner) -> Result<(), String> {
+        self.apply(runner).and_then(|output| match output {
+            Some(output) if !output.success() => {
+                let code = output
+                    .code
+                    .map(|code| code.to_string())
+                    .unwrap_or_else(|| "none".to_string());
+                Err(format!(
+                    "exited with code {}: {}",
+                    code,
+                    String::from_utf8_lossy(&output.stderr).trim()
+                ))
+            }
+            _ => Ok(()),
+        })
+    }
+
+    // Performs the step, returning the command output for `Run` steps
+    fn apply(&mut self, runner: &mut dyn CommandRunner) -> Result<Option<CommandOutput>, String> {
+        let result = match self {
+            Step::CreateDir(path) => fs::create_dir_all(path),
+            Step::WriteFile { path, contents } => fs::write(path, contents),
+            Step::CopyFile { src, dest } => copy_file(src, dest),
+            Step::RenderFile { src, dest, data } => render_file(src, dest, data),
+            Step::RemoveFile(path) => match fs::remove_file(path) {
+                Err(e) if e.kind() != ErrorKind::NotFound => Err(e),
+                _ => Ok(()),
+            },
+            Step::Run(command) => {
+                let output = runner.run(command).map_err(|e| e.to_string())?;
+                println!("->> STDOUT: {}", String::from_utf8_lossy(&output.stdout));
+                println!("->> STDERR: {}", String::from_utf8_lossy(&output.stderr));
+                return Ok(Some(output));
+            }
+        };
+        result.map(|_| None).map_err(|e| e.to_string())
+    }
+}
+
+fn copy_file(src: &Path, dest: &Path) -> io::Result<()> {
+    if let Some(parent) = dest.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::copy(src, dest).map(|_| ())
+}
+
+fn render_file(src: &Path, dest: &Path, data: &Value) -> io::Result<()> {
+    if let Some(parent) = dest.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let template = fs::read_to_string(src)?;
+    template_generator::render_to_file(&template, data, &mut fs::File::create(dest)?)
+        .map_err(|e| io::Error::new(ErrorKind::InvalidData, e.to_string()))
+}
+
+impl fmt::Display for Plan {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (index, step) in self.steps.iter().enumerate() {
+            writeln!(f, "{:>3}. {}", index + 1, step)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Step {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Step::CreateDir(path) => write!(f, "mkdir {}", path.display()),
+            Step::WriteFile { path, .. } => write!(f, "write {}", path.display()),
+            Step::CopyFile { src, dest } => {
+                write!(f, "copy {} -> {}", src.display(), dest.display())
+            }
+            Step::RenderFile { src, dest, data } => write!(
+                f,
+                "render {} -> {} with {}",
+                src.display(),
+                dest.display(),
+                data
+            ),
+            Step::RemoveFile(path) => write!(f, "remove {}", path.display()),
+            Step::Run(command) => {
+                write!(f, "run")?;
+                if let Some(dir) = command.get_current_dir() {
+                    write!(f, " in {}:", dir.display())?;
+                }
+                for (key, value) in command.get_envs() {
+                    let value = value.map(|v| v.to_string_lossy()).unwrap_or_default();
+                    write!(f, " {}={}", key.to_string_lossy(), value)?;
+                }
+                write!(f, " {}", command.get_program().to_string_lossy())?;
+                for arg in command.get_args() {
+                    write!(f, " {}", quote(&arg.to_string_lossy()))?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+// Wraps args containing whitespace in quotes so the printed plan can be copied into a shell
+fn quote(arg: &str) -> String {
+    if arg.contains(char::is_whitespace) {
+        format!("{:?}", arg)
+    } else {
+        arg.to_string()
+    }
+}
@@ -0,0 +1,176 @@
+use std::{io::Result, process::Command};
+
+#[cfg(test)]
+use std::{collections::VecDeque, path::PathBuf};
+
+/// Exit code and captured output of a finished command
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CommandOutput {
+    pub code: Option<i32>,
+    pub stdout: Vec<u8>,
+    pub stderr: Vec<u8>,
+}
+
+impl CommandOutput {
+    pub fn success(&self) -> bool {
+        self.code == Some(0)
+    }
+}
+
+/// Executes the external commands queued by the project builder
+pub trait CommandRunner {
+    /// Runs the command to completion, capturing its output
+    fn run(&mut self, command: &mut Command) -> Result<CommandOutput>;
+}
+
+/// Spawns real processes
+pub struct RealRunner;
+
+impl CommandRunner for RealRunner {
+    fn run(&mut self, command: &mut Command) -> Result<CommandOutput> {
+        let output = command.output()?;
+        Ok(CommandOutput {
+            code: output.status.code(),
+            stdout: output.stdout,
+            stderr: output.stderr,
+        })
+    }
+}
+
+/// Everything needed to reproduce a command, captured from its builder
+#[cfg(test)]
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecordedCommand {
+    pub program: String,
+    pub args: Vec<String>,
+    pub cwd: Option<PathBuf>,
+    pub env: Vec<(String, Option<String>)>,
+}
+
+#[cfg(test)]
+impl From<&Command> for RecordedCommand {
+    fn from(command: &Command) -> Self {
+        Self {
+            program: command.get_program().to_string_lossy().to_string(),
+            args: command
+                .get_args()
+                .map(|arg| arg.to_string_lossy().to_string())
+                .collect(),
+            cwd: command.get_current_dir().map(|dir| dir.to_path_buf()),
+            env: command
+                .get_envs()
+                .map(|(key, value)| {
+                    (
+                        key.to_string_lossy().to_string(),
+                        value.map(|v| v.to_string_lossy().to_string()),
+                    )
+                })
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+impl RecordedCommand {
+    /// The program and its args joined with spaces, handy for asserting on plans
+    pub fn line(&self) -> String {
+        std::iter::once(self.program.as_str())
+            .chain(self.args.iter().map(|arg| arg.as_str()))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+/// Records every command without running it and reports success
+#[cfg(test)]
+#[derive(Debug, Default)]
+pub struct RecordingRunner {
+    pub commands: Vec<RecordedCommand>,
+}
+
+#[cfg(test)]
+impl CommandRunner for RecordingRunner {
+    fn run(&mut self, command: &mut Command) -> Result<CommandOutput> {
+        self.commands.push(RecordedCommand::from(&*command));
+        Ok(CommandOutput {
+            code: Some(0),
+            ..Default::default()
+        })
+    }
+}
+
+/// Records every command and answers with canned outputs in order, succeeding once the script runs out
+#[cfg(test)]
+#[derive(Debug, Default)]
+pub struct ScriptedRunner {
+    pub commands: Vec<RecordedCommand>,
+    responses: VecDeque<CommandOutput>,
+}
+
+#[cfg(test)]
+impl ScriptedRunner {
+    pub fn respond(mut self, code: i32, stdout: &str) -> Self {
+        self.responses.push_back(CommandOutput {
+            code: Some(code),
+            stdout: stdout.as_bytes().to_vec(),
+            stderr: vec![],
+        });
+        self
+    }
+}
+
+#[cfg(test)]
+impl CommandRunner for ScriptedRunner {
+    fn run(&mut self, command: &mut Command) -> Result<CommandOutput> {
+        self.commands.push(RecordedCommand::from(&*command));
+        Ok(self.responses.pop_front().unwrap_or(CommandOutput {
+            code: Some(0),
+            ..Default::default()
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scripted_runner_replays_responses_in_order() {
+        let mut runner = ScriptedRunner::default()
+            .respond(101, "first")
+            .respond(0, "second");
+
+        let first = runner.run(&mut Command::new("cargo")).unwrap();
+        let second = runner.run(&mut Command::new("bun")).unwrap();
+        let fallback = runner.run(&mut Command::new("git")).unwrap();
+
+        assert!(!first.success());
+        assert_eq!(first.stdout, b"first");
+        assert!(second.success());
+        assert_eq!(second.stdout, b"second");
+        assert!(fallback.success());
+
+        let programs: Vec<_> = runner.commands.iter().map(|c| c.program.as_str()).collect();
+        assert_eq!(programs, ["cargo", "bun", "git"]);
+    }
+
+    #[test]
+    fn recorded_command_captures_cwd_and_env() {
+        let mut command = Command::new("cargo");
+        command
+            .args(["add", "serde"])
+            .current_dir("my-app")
+            .env("CARGO_NET_GIT_FETCH_WITH_CLI", "true");
+
+        let recorded = RecordedCommand::from(&command);
+        assert_eq!(recorded.line(), "cargo add serde");
+        assert_eq!(recorded.cwd, Some(PathBuf::from("my-app")));
+        assert_eq!(
+            recorded.env,
+            vec![(
+                "CARGO_NET_GIT_FETCH_WITH_CLI".to_string(),
+                Some("true".to_string())
+            )]
+        );
+    }
+}
@@ -0,0 +1,194 @@
+use std::{
+    fs,
+    path::{Component, Path, PathBuf},
+};
+
+use crate::{
+    config::{Language, TemplateEngine},
+    error::{GerminateError, Result},
+    toml_parser::TomlTemplate,
+};
+
+pub const STACK_TEMPLATE: &str = "stack_template.toml";
+
+/// A stack found in the templates directory, identified by the name of its folder
+#[derive(Debug, Clone)]
+pub struct Stack {
+    pub id: String,
+    pub dir: PathBuf,
+    pub template: TomlTemplate,
+    pub languages: Vec<Language>,
+    pub linters: Vec<String>,
+    pub frontend_linters: Vec<String>,
+    pub template_engines: Vec<TemplateEngine>,
+    pub supports_frontend: bool,
+    pub supports_containers: bool,
+    pub supports_db: bool,
+}
+
+/// The stacks found in a templates directory, and the stack folders that failed to load
+#[derive(Debug, Default)]
+pub struct Discovery {
+    pub stacks: Vec<Stack>,
+    pub warnings: Vec<String>,
+}
+
+impl Stack {
+    pub fn load(dir: &Path) -> Result<Self> {
+        let id = dir
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let template = TomlTemplate::new(&dir.join(STACK_TEMPLATE))?;
+        let stack = template.get_stack().clone();
+
+        Ok(Self {
+            id,
+            dir: dir.to_path_buf(),
+            languages: stack.languages,
+            linters: stack.linters,
+            frontend_linters: stack.frontend_linters,
+            template_engines: stack.template_engines,
+            supports_frontend: stack.supports_frontend,
+            supports_containers: stack.supports_containers,
+            supports_db: stack.supports_db,
+            template,
+        })
+    }
+
+    /// Every folder in `templates_dir` with a `stack_template.toml`, sorted by id. A broken
+    /// custom stack is reported as a warning rather than hiding every other stack
+    pub fn discover(templates_dir: &Path) -> Result<Discovery> {
+        let entries = fs::read_dir(templates_dir).map_err(GerminateError::io(format!(
+            "Failed to read templates directory {}",
+            templates_dir.display()
+        )))?;
+        let mut dirs = vec![];
+        for entry in entries {
+            let entry = entry.map_err(GerminateError::io(format!(
+                "Failed to read templates directory {}",
+                templates_dir.display()
+            )))?;
+            if entry.path().join(STACK_TEMPLATE).is_file() {
+                dirs.push(entry.path());
+            }
+        }
+        dirs.sort();
+
+        let mut discovery = Discovery::default();
+        for dir in dirs {
+            match Self::load(&dir) {
+                Ok(stack) => discovery.stacks.push(stack),
+                Err(e) => discovery.warnings.push(format!("skipping stack: {}", e)),
+            }
+        }
+        Ok(discovery)
+    }
+
+    pub fn find(templates_dir: &Path, id: &str) -> Result<Self> {
+        // ids are folder names, so `--stack ../x` can't reach outside the templates directory
+        let mut components = Path::new(id).components();
+        let is_folder_name = matches!(
+            (components.next(), components.next()),
+            (Some(Component::Normal(_)), None)
+        );
+        if !is_folder_name || id.contains(['/', '\\']) {
+            return Err(GerminateError::Config(format!(
+                "`{}` isn't a stack id, use the name of a folder in {}",
+                id,
+                templates_dir.display()
+            )));
+        }
+
+        let dir = templates_dir.join(id);
+        if dir.join(STACK_TEMPLATE).is_file() {
+            return Self::load(&dir);
+        }
+
+        let available = Self::discover(templates_dir)?
+            .stacks
+            .into_iter()
+            .map(|stack| stack.id)
+            .collect::<Vec<_>>();
+        Err(GerminateError::Config(format!(
+            "no stack named `{}` in {} (available: {})",
+            id,
+            templates_dir.display(),
+            available.join(", ")
+        )))
+    }
+
+    /// The menu entry for the stack, from its `title` and `description`
+    pub fn label(&self) -> String {
+        match self.template.get_description() {
+            "" => self.template.get_title().to_string(),
+            description => format!("{} - {}", self.template.get_title(), description),
+        }
+    }
+
+    pub fn has_language(&self, language: &Language) -> bool {
+        self.languages.contains(language)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn discovers_stacks_in_templates_dir() {
+        let stacks = Stack::discover(Path::new("templates"))
+            .expect("Failed to discover stacks")
+            .stacks;
+        let ids = stacks.iter().map(|s| s.id.as_str()).collect::<Vec<_>>();
+        assert_eq!(ids, ["rsapi", "rscli", "tsapi", "tscli"]);
+
+        let rsapi = &stacks[0];
+        assert_eq!(rsapi.label(), "rsapi - Rust Web App");
+        assert_eq!(rsapi.languages, [Language::Rust]);
+        assert_eq!(rsapi.linters, ["clippy", "rustfmt"]);
+        assert!(rsapi.supports_frontend && rsapi.supports_containers && rsapi.supports_db);
+
+        let tscli = &stacks[3];
+        assert_eq!(
+            tscli.languages,
+            [Language::TypeScript, Language::JavaScript]
+        );
+        assert!(!tscli.supports_frontend && !tscli.supports_containers && tscli.supports_db);
+    }
+
+    #[test]
+    fn discovers_custom_stack() {
+        let templates = TempDir::new().unwrap();
+        let worker = templates.path().join("pyworker");
+        fs::create_dir_all(&worker).unwrap();
+        fs::create_dir_all(templates.path().join("not-a-stack")).unwrap();
+        fs::write(
+            worker.join(STACK_TEMPLATE),
+            "title = \"pyworker\"\ndescription = \"Python queue worker\"\n\n[stack]\nsupports_containers = true\n",
+        )
+        .unwrap();
+
+        let broken = templates.path().join("broken");
+        fs::create_dir_all(&broken).unwrap();
+        fs::write(broken.join(STACK_TEMPLATE), "description = \"no title\"\n").unwrap();
+
+        let Discovery { stacks, warnings } = Stack::discover(templates.path()).unwrap();
+        assert_eq!(stacks.len(), 1);
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("broken"), "{}", warnings[0]);
+        assert_eq!(stacks[0].label(), "pyworker - Python queue worker");
+        assert!(stacks[0].languages.is_empty());
+        assert!(stacks[0].supports_containers && !stacks[0].supports_db);
+
+        let missing = Stack::find(templates.path(), "gosvc").unwrap_err();
+        assert!(missing.to_string().contains("available: pyworker"));
+
+        for id in ["../pyworker", "pyworker/../pyworker", "..", "/tmp", ""] {
+            let error = Stack::find(&worker.join("sub"), id).unwrap_err();
+            assert!(matches!(error, GerminateError::Config(_)), "{}", id);
+            assert!(error.to_string().contains("isn't a stack id"), "{}", error);
+        }
+    }
+}
@@ -0,0 +1,109 @@
+use std::{
+    fs,
+    io::{Error, ErrorKind, Result},
+    path::{Path, PathBuf},
+};
+
+const STAGING_DIR: &str = ".germinate-staging";
+
+/// A directory the project is built in before being renamed into place, named like the project
+/// so that `cargo init` and `npm init` pick up a valid package name.
+/// Dropping it without `commit` removes it, including while unwinding from a panic.
+pub struct StagingDir {
+    path: PathBuf,
+    keep_on_failure: bool,
+    committed: bool,
+}
+
+impl StagingDir {
+    pub fn new(output_dir: &Path, keep_on_failure: bool) -> Result<Self> {
+        if output_dir.exists() {
+            return Err(Error::new(
+                ErrorKind::AlreadyExists,
+                format!("Output directory already exists: {}", output_dir.display()),
+            ));
+        }
+
+        let path = Self::path_for(output_dir);
+        if path.exists() {
+            return Err(Error::new(
+                ErrorKind::AlreadyExists,
+                format!(
+                    "Staging directory from a previous build already exists: {} (run `germinate resume {}` or remove it)",
+                    path.display(),
+                    output_dir.display()
+                ),
+            ));
+        }
+
+        Ok(Self {
+            path,
+            keep_on_failure,
+            committed: false,
+        })
+    }
+
+    /// Reopens the staging directory left behind by an interrupted build
+    pub fn existing(output_dir: &Path, keep_on_failure: bool) -> Result<Self> {
+        let path = Self::path_for(output_dir);
+        if !path.is_dir() {
+            return Err(Error::new(
+                ErrorKind::NotFound,
+                format!("No interrupted build found at {}", path.display()),
+            ));
+        }
+        if output_dir.exists() {
+            return Err(Error::new(
+                ErrorKind::AlreadyExists,
+                format!("Output directory already exists: {}", output_dir.display()),
+            ));
+        }
+
+        Ok(Self {
+            path,
+            keep_on_failure,
+            committed: false,
+        })
+    }
+
+    /// The staging path lives next to the output so the final rename stays on one filesystem:
+    /// `<parent>/.germinate-staging/<name>`
+    pub fn path_for(output_dir: &Path) -> PathBuf {
+        let name = output_dir.file_name().unwrap_or_default();
+        output_dir.with_file_name(STAGING_DIR).join(name)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Atomically moves the finished build to its final location
+    pub fn commit(mut self, output_dir: &Path) -> Result<()> {
+        fs::rename(&self.path, output_dir)?;
+        self.committed = true;
+        self.remove_parent();
+        Ok(())
+    }
+
+    // other builds may still be staged next to this one, so the parent only goes once it's empty
+    fn remove_parent(&self) {
+        if let Some(parent) = self.path.parent() {
+            fs::remove_dir(parent).ok();
+        }
+    }
+}
+
+impl Drop for StagingDir {
+    fn drop(&mut self) {
+        if self.committed || !self.path.exists() {
+            return;
+        }
+        if self.keep_on_failure {
+            eprintln!("Keeping staging directory: {}", self.path.display());
+        } else {
+            println!("Cleaning up staging directory: {}", self.path.display());
+            fs::remove_dir_all(&self.path).ok();
+            self.remove_parent();
+        }
+    }
+}
@@ -1,44 +1,116 @@
-use std::{
-    fs::{self, File},
-    path::PathBuf,
-};
+use std::{ffi::OsStr, fs::File, io, path::Path};
 
 use handlebars::{Handlebars, RenderError};
 use serde::Serialize;
+use serde_json::Value;
+
+use crate::{config::ScaffoldConfig, file_system, package_manager::PackageManager, plan::Step};
 
 pub fn render_to_file<T>(template: &str, data: &T, file: &mut File) -> Result<(), RenderError>
 where
     T: Serialize,
 {
-    let handlebars = Handlebars::new();
+    let mut handlebars = Handlebars::new();
+    // the templates are source and config files, not HTML, so values are written as given
+    handlebars.register_escape_fn(handlebars::no_escape);
     handlebars.render_template_to_write(template, data, file)?;
     Ok(())
 }
 
 pub trait TemplateData {}
 
-pub fn generate_dir<T>(src: PathBuf, dest: PathBuf, data: &T, recursive: bool)
+/// Data available to the `after_install` and linter config templates
+#[derive(Debug, Serialize)]
+pub struct ProjectVariables {
+    pub app_name: String,
+    pub spa: bool,
+    pub template_engine: bool,
+    /// `bun`, `npm`, `pnpm` or `yarn`, e.g. `{{#if (eq package_manager "bun")}}`
+    pub package_manager: PackageManager,
+}
+
+impl TemplateData for ProjectVariables {}
+
+impl ProjectVariables {
+    pub fn new(config: &ScaffoldConfig) -> Self {
+        Self {
+            app_name: config.user_options.app_name.clone(),
+            spa: config.user_options.spa.is_some(),
+            template_engine: config.user_options.template_engine.is_some(),
+            package_manager: config.user_options.package_manager,
+        }
+    }
+}
+
+/// The extension that marks a file as a handlebars template where rendering is opt-in
+pub const TEMPLATE_EXTENSION: &str = "hbs";
+
+pub fn is_template(file: &Path) -> bool {
+    file.extension() == Some(OsStr::new(TEMPLATE_EXTENSION))
+}
+
+/// Queues a render step for every file under `src`, mirroring the folder structure into `dest`
+pub fn plan_dir<T>(src: &Path, dest: &Path, data: &T) -> io::Result<Vec<Step>>
 where
     T: TemplateData + Serialize,
 {
-    fs::create_dir_all(&dest).expect("Failed to create directory");
-    for file in fs::read_dir(src).unwrap() {
-        if let Ok(file) = file {
-            if file.file_type().unwrap().is_dir() && recursive {
-                println!("Generating directory: {:?}", file.file_name());
-                let new_dest = dest.join(file.file_name().into_string().unwrap());
-                generate_dir(file.path(), new_dest, data, recursive);
-            } else {
-                println!("Generating file: {:?}", dest.join(file.file_name()));
-                let template = fs::read_to_string(file.path()).expect("Failed to read template");
-                let new_file = dest.join(file.file_name());
-                crate::template_generator::render_to_file(
-                    &template,
-                    data,
-                    &mut fs::File::create(new_file).unwrap(),
-                )
-                .expect((format!("Failed to render template: {:?}", &file)).as_str());
-            }
-        }
+    let data: Value = serde_json::to_value(data)?;
+    let steps = file_system::list_files(src)?
+        .into_iter()
+        .map(|file| Step::RenderFile {
+            src: src.join(&file),
+            dest: dest.join(&file),
+            data: data.clone(),
+        })
+        .collect();
+    Ok(steps)
+}
+
+/// Like `plan_dir`, but only `.hbs` files are rendered, to `dest` without the suffix.
+/// The other files are copied as is
+pub fn plan_templates<T>(src: &Path, dest: &Path, data: &T) -> io::Result<Vec<Step>>
+where
+    T: TemplateData + Serialize,
+{
+    let data: Value = serde_json::to_value(data)?;
+    let steps = file_system::list_files(src)?
+        .into_iter()
+        .map(|file| match is_template(&file) {
+            true => Step::RenderFile {
+                src: src.join(&file),
+                dest: dest.join(file.with_extension("")),
+                data: data.clone(),
+            },
+            false => Step::CopyFile {
+                src: src.join(&file),
+                dest: dest.join(&file),
+            },
+        })
+        .collect();
+    Ok(steps)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use serde_json::json;
+    use tempfile::TempDir;
+
+    use super::*;
+
+    #[test]
+    fn renders_values_unescaped() {
+        let out = TempDir::new().unwrap();
+        let path = out.path().join("Cargo.toml");
+        let mut file = File::create(&path).unwrap();
+        let data = json!({ "app_name": "Tom's \"R&D\" <app>" });
+
+        render_to_file("description = '{{app_name}}'", &data, &mut file).unwrap();
+
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "description = 'Tom's \"R&D\" <app>'"
+        );
     }
 }
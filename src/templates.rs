@@ -0,0 +1,347 @@
+use std::{
+    env, fmt, fs,
+    path::{Path, PathBuf},
+};
+
+use include_dir::{include_dir, Dir, DirEntry};
+use serde::Deserialize;
+
+use crate::error::{GerminateError, Result};
+
+pub const TEMPLATES_ENV: &str = "GERMINATE_TEMPLATES";
+const CONFIG_FILE: &str = "germinate/config.toml";
+const DATA_TEMPLATES: &str = "germinate/templates";
+const CACHE_DIR: &str = "germinate";
+
+/// The stock stacks, so an installed binary works without a templates folder on disk
+static EMBEDDED: Dir = include_dir!("$CARGO_MANIFEST_DIR/templates");
+
+/// Where the templates directory was found, in order of precedence
+#[derive(Debug, Clone, PartialEq)]
+pub enum TemplatesSource {
+    Flag,
+    Env,
+    ConfigFile(PathBuf),
+    DataDir,
+    Binary,
+    Embedded,
+}
+
+impl fmt::Display for TemplatesSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Flag => write!(f, "--templates-dir"),
+            Self::Env => write!(f, "{} environment variable", TEMPLATES_ENV),
+            Self::ConfigFile(path) => write!(f, "config file {}", path.display()),
+            Self::DataDir => write!(f, "user data directory"),
+            Self::Binary => write!(f, "next to the germinate binary"),
+            Self::Embedded => write!(f, "templates built into germinate"),
+        }
+    }
+}
+
+/// The folder holding one sub folder per stack, each with a `stack_template.toml`
+#[derive(Debug)]
+pub struct TemplatesDir {
+    pub path: PathBuf,
+    pub source: TemplatesSource,
+}
+
+// `templates_dir` in `$XDG_CONFIG_HOME/germinate/config.toml`
+#[derive(Debug, Default, Deserialize)]
+struct UserConfig {
+    templates_dir: Option<PathBuf>,
+}
+
+impl TemplatesDir {
+    /// Resolves the templates directory from the flag, `GERMINATE_TEMPLATES`, the user config file,
+    /// `$XDG_DATA_HOME/germinate/templates`, the folder next to the binary and finally the embedded stacks
+    pub fn resolve(flag: Option<&Path>) -> Result<Self> {
+        let exe = env::current_exe().map_err(GerminateError::io("Failed to locate germinate"))?;
+        let exe_dir = exe.parent().unwrap_or(Path::new("")).to_path_buf();
+        Self::resolve_with(flag, |key| env::var_os(key).map(PathBuf::from), &exe_dir)
+    }
+
+    fn resolve_with(
+        flag: Option<&Path>,
+        var: impl Fn(&str) -> Option<PathBuf>,
+        exe_dir: &Path,
+    ) -> Result<Self> {
+        if let Some(path) = flag {
+            return Self::explicit(path.to_path_buf(), TemplatesSource::Flag);
+        }
+        if let Some(path) = var(TEMPLATES_ENV).filter(|path| !path.as_os_str().is_empty()) {
+            return Self::explicit(path, TemplatesSource::Env);
+        }
+
+        let home = var("HOME");
+        let config_file = var("XDG_CONFIG_HOME")
+            .or_else(|| home.as_ref().map(|home| home.join(".config")))
+            .map(|dir| dir.join(CONFIG_FILE));
+        if let Some(config_file) = config_file.filter(|file| file.is_file()) {
+            if let Some(path) = Self::read_config(&config_file)? {
+                return Self::explicit(path, TemplatesSource::ConfigFile(config_file));
+            }
+        }
+
+        let data_dir = var("XDG_DATA_HOME")
+            .or_else(|| home.as_ref().map(|home| home.join(".local/share")))
+            .map(|dir| dir.join(DATA_TEMPLATES));
+        if let Some(path) = data_dir.filter(|dir| dir.is_dir()) {
+            return Ok(Self::on_disk(path, TemplatesSource::DataDir));
+        }
+
+        let path = exe_dir.join("templates");
+        if path.is_dir() {
+            return Ok(Self::on_disk(path, TemplatesSource::Binary));
+        }
+
+        let cache_dir = var("XDG_CACHE_HOME")
+            .filter(|path| path.is_absolute())
+            .or_else(|| home.as_ref().map(|home| home.join(".cache")))
+            .unwrap_or_else(env::temp_dir)
+            .join(CACHE_DIR);
+        Self::embedded(&cache_dir)
+    }
+
+    fn on_disk(path: PathBuf, source: TemplatesSource) -> Self {
+        Self { path, source }
+    }
+
+    // plans copy from disk, so the embedded templates are extracted once per version and content.
+    // The path has to stay the same between runs for `resume` to match the journaled steps
+    fn embedded(cache_dir: &Path) -> Result<Self> {
+        let path = cache_dir.join(format!(
+            "templates-{}-{:016x}",
+            env!("CARGO_PKG_VERSION"),
+            embedded_hash()
+        ));
+        if !path.is_dir() {
+            Self::extract_to(cache_dir, &path)?;
+        }
+        Ok(Self::on_disk(path, TemplatesSource::Embedded))
+    }
+
+    // extracted next to `path` and renamed into place, so an interrupted run never leaves half a copy
+    fn extract_to(cache_dir: &Path, path: &Path) -> Result<()> {
+        fs::create_dir_all(cache_dir).map_err(GerminateError::io(format!(
+            "Failed to create {}",
+            cache_dir.display()
+        )))?;
+        let extracting = tempfile::Builder::new()
+            .prefix(".extracting-")
+            .tempdir_in(cache_dir)
+            .map_err(GerminateError::io(
+                "Failed to create a folder for the built in templates",
+            ))?;
+        EMBEDDED
+            .extract(extracting.path())
+            .map_err(GerminateError::io(
+                "Failed to extract the built in templates",
+            ))?;
+        // another run may have extracted the same templates in the meantime, which is just as good
+        match fs::rename(extracting.path(), path) {
+            Err(e) if !path.is_dir() => Err(GerminateError::io(format!(
+                "Failed to move the built in templates to {}",
+                path.display()
+            ))(e)),
+            _ => Ok(()),
+        }
+    }
+
+    /// Writes the built in templates to `dir` so they can be customized
+    pub fn export(dir: &Path) -> Result<()> {
+        let is_empty = dir
+            .read_dir()
+            .map(|mut entries| entries.next().is_none())
+            .unwrap_or(true);
+        if !is_empty {
+            return Err(GerminateError::Config(format!(
+                "{} is not empty, export the templates to a new folder",
+                dir.display()
+            )));
+        }
+        fs::create_dir_all(dir).map_err(GerminateError::io(format!(
+            "Failed to create {}",
+            dir.display()
+        )))?;
+        EMBEDDED.extract(dir).map_err(GerminateError::io(format!(
+            "Failed to export the templates to {}",
+            dir.display()
+        )))
+    }
+
+    // a directory the user asked for by name has to exist, rather than silently falling through
+    fn explicit(path: PathBuf, source: TemplatesSource) -> Result<Self> {
+        if !path.is_dir() {
+            return Err(GerminateError::Config(format!(
+                "templates directory {} from {} does not exist",
+                path.display(),
+                source
+            )));
+        }
+        Ok(Self::on_disk(path, source))
+    }
+
+    // relative paths in the config file are relative to the file itself
+    fn read_config(config_file: &Path) -> Result<Option<PathBuf>> {
+        let source = format!("config file {}", config_file.display());
+        let contents = fs::read_to_string(config_file)
+            .map_err(GerminateError::io(format!("Failed to read {}", source)))?;
+        let config: UserConfig =
+            toml::from_str(&contents).map_err(|e| GerminateError::input(&source, e))?;
+        Ok(config.templates_dir.map(|dir| {
+            config_file
+                .parent()
+                .map(|parent| parent.join(&dir))
+                .unwrap_or(dir)
+        }))
+    }
+}
+
+/// FNV-1a over every embedded path and file, so a rebuilt binary with changed templates
+/// doesn't reuse a stale extraction of the same version
+fn embedded_hash() -> u64 {
+    fn visit(dir: &Dir, hash: &mut u64) {
+        for entry in dir.entries() {
+            let path = entry.path().to_string_lossy();
+            let contents = match entry {
+                DirEntry::File(file) => file.contents(),
+                DirEntry::Dir(_) => &[],
+            };
+            for byte in path.as_bytes().iter().chain(contents) {
+                *hash = (*hash ^ u64::from(*byte)).wrapping_mul(0x100000001b3);
+            }
+            if let DirEntry::Dir(dir) = entry {
+                visit(dir, hash);
+            }
+        }
+    }
+
+    let mut hash = 0xcbf29ce484222325;
+    visit(&EMBEDDED, &mut hash);
+    hash
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{config::ScaffoldConfig, dialogue::UserOptions, stack::Stack};
+    use std::collections::HashMap;
+    use tempfile::TempDir;
+
+    fn resolve(
+        flag: Option<&Path>,
+        vars: &[(&str, &Path)],
+        exe_dir: &Path,
+    ) -> Result<TemplatesDir> {
+        let vars: HashMap<String, PathBuf> = vars
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_path_buf()))
+            .collect();
+        TemplatesDir::resolve_with(flag, |key| vars.get(key).cloned(), exe_dir)
+    }
+
+    #[test]
+    fn resolves_sources_in_order() {
+        let home = TempDir::new().unwrap();
+        let flag = home.path().join("flag");
+        let env_dir = home.path().join("env");
+        let shared = home.path().join("shared");
+        let data = home.path().join(".local/share").join(DATA_TEMPLATES);
+        for dir in [&flag, &env_dir, &shared, &data] {
+            fs::create_dir_all(dir).unwrap();
+        }
+        let config_file = home.path().join(".config").join(CONFIG_FILE);
+        fs::create_dir_all(config_file.parent().unwrap()).unwrap();
+        fs::write(&config_file, "templates_dir = \"../../shared\"\n").unwrap();
+        let exe_dir = home.path().join("bin");
+        let exe_templates = exe_dir.join("templates");
+        fs::create_dir_all(&exe_templates).unwrap();
+        let exe_dir = exe_dir.as_path();
+
+        let all = [("HOME", home.path()), (TEMPLATES_ENV, env_dir.as_path())];
+        let found = resolve(Some(&flag), &all, exe_dir).unwrap();
+        assert_eq!((found.path, found.source), (flag, TemplatesSource::Flag));
+
+        let found = resolve(None, &all, exe_dir).unwrap();
+        assert_eq!((found.path, found.source), (env_dir, TemplatesSource::Env));
+
+        let found = resolve(None, &[("HOME", home.path())], exe_dir).unwrap();
+        assert_eq!(
+            found.source,
+            TemplatesSource::ConfigFile(config_file.clone())
+        );
+        assert_eq!(
+            found.path.canonicalize().unwrap(),
+            shared.canonicalize().unwrap()
+        );
+
+        fs::remove_file(&config_file).unwrap();
+        let found = resolve(None, &[("HOME", home.path())], exe_dir).unwrap();
+        assert_eq!(
+            (found.path, found.source),
+            (data.clone(), TemplatesSource::DataDir)
+        );
+
+        fs::remove_dir_all(&data).unwrap();
+        let found = resolve(None, &[("HOME", home.path())], exe_dir).unwrap();
+        assert_eq!(
+            (found.path, found.source),
+            (exe_templates.clone(), TemplatesSource::Binary)
+        );
+
+        fs::remove_dir_all(&exe_templates).unwrap();
+        let found = resolve(None, &[("HOME", home.path())], exe_dir).unwrap();
+        assert_eq!(found.source, TemplatesSource::Embedded);
+        assert!(found
+            .path
+            .starts_with(home.path().join(".cache").join(CACHE_DIR)));
+        assert!(found.path.join("rsapi/stack_template.toml").is_file());
+
+        // journaled steps name template paths, so every run has to extract to the same place
+        let again = resolve(None, &[("HOME", home.path())], exe_dir).unwrap();
+        assert_eq!(again.path, found.path);
+        assert_eq!(
+            fs::read_dir(found.path.parent().unwrap()).unwrap().count(),
+            1
+        );
+    }
+
+    #[test]
+    fn rejects_missing_explicit_dir() {
+        let home = TempDir::new().unwrap();
+        let missing = home.path().join("missing");
+        let result = resolve(None, &[(TEMPLATES_ENV, &missing)], home.path());
+        assert!(matches!(result, Err(GerminateError::Config(_))));
+    }
+
+    #[test]
+    fn exports_every_stock_stack() {
+        let out = TempDir::new().unwrap();
+        let dir = out.path().join("templates");
+        TemplatesDir::export(&dir).expect("Failed to export templates");
+
+        let stacks = Stack::discover(&dir).unwrap().stacks;
+        assert_eq!(stacks.len(), 4);
+        for stack in stacks {
+            let options = UserOptions {
+                stack: stack.id,
+                output_dir: out.path().join("app"),
+                app_name: "app".to_string(),
+                db: None,
+                orm: false,
+                db_client: None,
+                spa: None,
+                template_engine: None,
+                containers: false,
+                package_manager: Default::default(),
+            };
+            ScaffoldConfig::new(options, &dir).expect("Exported stack failed to load");
+        }
+        assert!(dir.join("rsapi/docker/docker-compose.yml").is_file());
+        assert!(dir.join("rsapi/before_install/.gitignore").is_file());
+
+        assert!(TemplatesDir::export(&dir).is_err());
+    }
+}
@@ -1,34 +1,177 @@
 use std::{
-    collections::HashMap,
+    collections::{BTreeMap, HashMap},
     fs,
     path::{Path, PathBuf},
 };
 
-use crate::config::PackageScripts;
-use crate::module::Module;
-use toml::{map::Map, Table, Value};
+use crate::config::{Language, PackageScripts, TemplateEngine};
+use crate::error::{GerminateError, Result};
+use crate::module::{CargoDependency, Module, NpmDependency, TemplateDependency};
+use crate::stack::STACK_TEMPLATE;
+use schemars::{
+    gen::{SchemaGenerator, SchemaSettings},
+    schema::{RootSchema, Schema},
+    JsonSchema,
+};
+use serde::{de::DeserializeOwned, Deserialize};
+use toml::{Spanned, Value};
 
 type Dependencies = HashMap<String, Option<Vec<Module>>>;
 type Scripts = HashMap<String, PackageScripts>;
+
+/// The `[stack]` table, declaring what a stack is built with and which options it offers
+#[derive(Debug, Clone, Default, Deserialize, JsonSchema)]
+#[serde(default, deny_unknown_fields)]
+pub struct StackTable {
+    pub languages: Vec<Language>,
+    /// Names of linters and formatters in the templates directory's `linters.toml`
+    pub linters: Vec<String>,
+    /// Added on top of `linters` when a SPA or template engine is chosen
+    pub frontend_linters: Vec<String>,
+    /// Engines offered for server rendered views, when the stack supports a frontend
+    pub template_engines: Vec<TemplateEngine>,
+    pub supports_frontend: bool,
+    pub supports_containers: bool,
+    pub supports_db: bool,
+}
+
+/// A stack's `stack_template.toml`, naming the stack and what to install and create for it
+// parsed as written, then flattened into a `TomlTemplate`
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+struct TemplateFile {
+    /// Name of the stack in the menu
+    title: String,
+    /// Shown next to the title in the menu
+    #[serde(default)]
+    description: String,
+    // a template without a `[stack]` table offers nothing beyond copying its files
+    #[serde(default)]
+    stack: StackTable,
+    /// Folders to create in the project, as nested tables where an empty table is a leaf
+    subfolders: Option<Folders>,
+    /// Scripts to add to the project, by package manager
+    scripts: Option<ScriptsTable>,
+    // stacks for other languages may install nothing through npm or cargo
+    /// Packages to install, by package manager
+    #[serde(default)]
+    deps: DepsTable,
+}
+
+/// A folder's children, where an empty table marks a leaf to create
+#[derive(Debug, Default, Deserialize)]
+#[serde(transparent)]
+struct Folders(BTreeMap<String, Folders>);
+
+impl Folders {
+    fn paths(&self, parent: &Path) -> Vec<PathBuf> {
+        self.0
+            .iter()
+            .flat_map(|(name, children)| {
+                let path = parent.join(name);
+                match children.0.is_empty() {
+                    true => vec![path],
+                    false => children.paths(&path),
+                }
+            })
+            .collect()
+    }
+}
+
+// derived schemas inline transparent types, which never ends for a recursive one
+impl JsonSchema for Folders {
+    fn schema_name() -> String {
+        "Folders".to_string()
+    }
+
+    fn json_schema(gen: &mut SchemaGenerator) -> Schema {
+        BTreeMap::<String, Folders>::json_schema(gen)
+    }
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+struct ScriptsTable {
+    npm: Option<PackageScripts>,
+    cargo: Option<PackageScripts>,
+}
+
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+struct DepsTable {
+    npm: Option<Vec<NpmDependency>>,
+    cargo: Option<Vec<CargoDependency>>,
+}
+
 #[derive(Debug, Clone)]
 pub struct TomlTemplate {
+    title: String,
+    description: String,
+    stack: StackTable,
     subfolders: Option<Vec<PathBuf>>,
     scripts: Option<Scripts>,
     dependencies: Dependencies,
+    warnings: Vec<String>,
+}
+
+/// JSON Schema for `stack_template.toml`, generated from the types it's parsed into
+pub fn schema() -> RootSchema {
+    // TOML has no null, so optional keys are just left out of `required`
+    let mut schema = SchemaSettings::draft07()
+        .with(|settings| settings.option_add_null_type = false)
+        .into_generator()
+        .into_root_schema_for::<TemplateFile>();
+    schema.schema.metadata().title = Some(STACK_TEMPLATE.to_string());
+    schema
 }
 
 impl TomlTemplate {
-    pub fn new(path: &Path) -> Self {
-        let table = Self::get_table(path);
-        let subfolders = Self::parse_subfolders(&table);
-        let scripts = Self::parse_scripts(&table);
-        let dependencies = Self::parse_deps(&table);
-
-        Self {
-            subfolders,
+    pub fn new(path: &Path) -> Result<Self> {
+        let source = read_file(path, "stack template")?;
+        Self::parse(path, &source)
+    }
+
+    fn parse(path: &Path, source: &str) -> Result<Self> {
+        let file = deserialize_template::<TemplateFile>(path, source)?;
+        let mut warnings = below_minimum(path, "npm", file.deps.npm.as_deref());
+        warnings.append(&mut below_minimum(
+            path,
+            "cargo",
+            file.deps.cargo.as_deref(),
+        ));
+
+        let scripts = file.scripts.map(|scripts| {
+            [("npm", scripts.npm), ("cargo", scripts.cargo)]
+                .into_iter()
+                .filter_map(|(key, scripts)| Some((key.to_string(), scripts?)))
+                .collect()
+        });
+        let dependencies = HashMap::from([
+            ("npm".to_string(), into_modules(file.deps.npm)),
+            ("cargo".to_string(), into_modules(file.deps.cargo)),
+        ]);
+
+        Ok(Self {
+            title: file.title,
+            description: file.description,
+            stack: file.stack,
+            subfolders: file.subfolders.map(|folders| folders.paths(Path::new(""))),
             scripts,
             dependencies,
-        }
+            warnings,
+        })
+    }
+
+    pub fn get_title(&self) -> &str {
+        &self.title
+    }
+
+    pub fn get_description(&self) -> &str {
+        &self.description
+    }
+
+    pub fn get_stack(&self) -> &StackTable {
+        &self.stack
     }
 
     pub fn get_subfolders(&self) -> Option<&Vec<PathBuf>> {
@@ -43,189 +186,127 @@ impl TomlTemplate {
         &self.dependencies
     }
 
-    fn parse_deps(table: &Table) -> Dependencies {
-        let deps = match table.get("deps") {
-            Some(deps) => deps.as_table().expect("Error parsing dependencies"),
-            None => panic!("No deps keys found in TOML template file"),
-        };
-
-        let package_managers = vec!["npm", "cargo"];
-        let parsed_deps = Self::fetch_deps(package_managers, deps);
-
-        parsed_deps
-    }
-
-    fn fetch_deps(keys: Vec<&str>, deps: &Table) -> Dependencies {
-        let mut results = HashMap::new();
-        keys.iter().for_each(|key| {
-            let packages = match deps.get(*key) {
-                Some(entries) => {
-                    let entries = entries
-                        .as_array()
-                        .expect(format!("Error retrieving {} dependencies", key).as_str());
-                    println!("Collecting {} dependencies", key);
-                    Some(Self::format_deps(entries))
-                }
-                None => None,
-            };
-            results.insert(key.to_string(), packages);
-        });
-        results
+    /// Problems that don't stop the stack from being built, like a `version` below `min_version`
+    pub fn get_warnings(&self) -> &[String] {
+        &self.warnings
     }
+}
 
-    fn format_deps(table: &Vec<Value>) -> Vec<Module> {
-        table
-            .iter()
-            .map(|dep| {
-                let dep = dep.as_table().expect("Error collecting deps");
-                let name = match dep.get("name") {
-                    Some(name) => name.as_str().expect("Error parsing name"),
-                    None => panic!("Error parsing name"),
-                };
-
-                //TODO: add semver crate to allow for parsing semver ranges
-                let version = match dep.get("version") {
-                    Some(version) => version.as_str().expect("Error parsing version"),
-                    None => "latest",
-                };
-
-                let dev = match dep.get("dev") {
-                    Some(dev) => dev.as_bool().expect("Error parsing dev"),
-                    None => false,
-                };
-
-                let then = match dep.get("then") {
-                    Some(cmds) => {
-                        let cmds = cmds
-                            .as_array()
-                            .expect("Error parsing then array")
-                            .iter()
-                            .map(|arr| arr.as_array().expect("Error parsing cmd"))
-                            .map(|cmd_arr| {
-                                cmd_arr
-                                    .iter()
-                                    .map(|arg| arg.as_str().expect("Error parsing arg").to_string())
-                                    .collect()
-                            })
-                            .collect();
-                        Some(cmds)
-                    }
-                    None => None,
-                };
-
-                let features = match dep.get("features") {
-                    Some(features) => Some(
-                        features
-                            .as_array()
-                            .expect("Error parsing dev")
-                            .iter()
-                            .map(|feature| {
-                                feature.as_str().expect("Error parsing feature").to_string()
-                            })
-                            .collect(),
-                    ),
-                    None => None,
-                };
-                Module::new(name.to_string(), version.to_string(), dev, then, features)
-            })
-            .collect()
-    }
+/// Reads a stack template or registry file, saying which kind of file it is if it can't be read
+pub(crate) fn read_file(path: &Path, kind: &str) -> Result<String> {
+    fs::read_to_string(path).map_err(GerminateError::io(format!(
+        "Failed to read {} {}",
+        kind,
+        path.display()
+    )))
+}
 
-    fn parse_scripts(table: &Map<String, Value>) -> Option<Scripts> {
-        let package_managers = vec!["npm", "cargo"];
-        match table.get("scripts") {
-            Some(scripts) => {
-                let scripts_table = scripts.as_table().expect("Error parsing dependencies");
-                Some(Self::format_scripts(package_managers, scripts_table))
+/// Deserializes a template file, pointing any error at the key, line and column it was found at
+pub(crate) fn deserialize_template<T: DeserializeOwned>(path: &Path, source: &str) -> Result<T> {
+    serde_path_to_error::deserialize(toml::Deserializer::new(source)).map_err(|error| {
+        let key = match error.path().to_string() {
+            root if root == "." => String::new(),
+            key => key,
+        };
+        let error = error.into_inner();
+        let message = with_hint(error.message());
+        match error.span() {
+            Some(span) => {
+                GerminateError::template_at(path, line_column(source, span.start), &key, message)
             }
-            None => None,
+            None => GerminateError::template(path, &key, message),
         }
-    }
+    })
+}
 
-    fn format_scripts(keys: Vec<&str>, table: &Table) -> Scripts {
-        let mut results = HashMap::new();
-        keys.iter().for_each(|key| {
-            match table.get(*key) {
-                Some(entries) => {
-                    let mut scripts = HashMap::new();
-                    entries
-                        .as_table()
-                        .expect("Error extracting npm scripts table")
-                        .iter()
-                        .for_each(|(key, val)| {
-                            scripts.insert(
-                                key.to_string(),
-                                val.as_str().expect("Error parsing script").to_string(),
-                            );
-                        });
-                    results.insert(key.to_string(), scripts);
-                }
-                None => (),
+/// Reads an array of dependency tables kept as a raw value, for registries where the format of
+/// the deps depends on another key. Errors point at the start of the array
+pub(crate) fn deserialize_deps<T: TemplateDependency>(
+    path: &Path,
+    source: &str,
+    key: &str,
+    deps: &Spanned<Value>,
+) -> Result<Vec<Module>> {
+    serde_path_to_error::deserialize::<_, Vec<T>>(deps.get_ref().clone())
+        .map(|deps| deps.into_iter().map(Into::into).collect())
+        .map_err(|error| {
+            let key = match error.path().to_string() {
+                root if root == "." => key.to_string(),
+                path if path.starts_with('[') => format!("{}{}", key, path),
+                path => format!("{}.{}", key, path),
             };
-        });
+            let location = line_column(source, deps.span().start);
+            GerminateError::template_at(path, location, &key, with_hint(error.inner().message()))
+        })
+}
 
-        results
-    }
+fn into_modules<T: TemplateDependency>(deps: Option<Vec<T>>) -> Option<Vec<Module>> {
+    deps.map(|deps| deps.into_iter().map(Into::into).collect())
+}
 
-    fn parse_subfolders(table: &Map<String, Value>) -> Option<Vec<PathBuf>> {
-        match table.get("subfolders") {
-            Some(subfolders) => {
-                let subfolders = subfolders.as_table().expect("Error parsing subfolders");
-                let mut paths: Vec<PathBuf> = vec![];
-                let path = Path::new("");
-                let mut child_paths: Vec<PathBuf> = subfolders
-                    .iter()
-                    .flat_map(|child| Self::get_sub_paths(child, &path))
-                    .collect();
-                paths.append(&mut child_paths);
-                Some(paths)
-            }
-            None => {
-                println!("No subfolders key in table");
-                None
-            }
-        }
-    }
+/// Warns about each dependency whose `version` allows releases older than its `min_version`
+fn below_minimum<T: TemplateDependency>(
+    path: &Path,
+    manager: &str,
+    deps: Option<&[T]>,
+) -> Vec<String> {
+    deps.into_iter()
+        .flatten()
+        .enumerate()
+        .filter_map(|(index, dep)| {
+            let message = dep.below_minimum()?;
+            Some(format!(
+                "{} at `deps.{}[{}].version`: {}",
+                path.display(),
+                manager,
+                index,
+                message
+            ))
+        })
+        .collect()
+}
 
-    fn get_sub_paths((name, children): (&String, &toml::Value), path: &Path) -> Vec<PathBuf> {
-        let mut paths: Vec<PathBuf> = vec![];
-        let children = children
-            .as_table()
-            .expect("Error parsing subfolder children");
-        if children.is_empty() {
-            paths.push(path.join(name));
-        } else {
-            let path = path.join(name);
-            let mut child_paths: Vec<PathBuf> = children
-                .iter()
-                .flat_map(|child| Self::get_sub_paths(child, &path))
-                .collect();
-            paths.append(&mut child_paths);
-        }
+/// The 1-based line and column of a byte offset
+fn line_column(source: &str, offset: usize) -> (usize, usize) {
+    let before = source.get(..offset).unwrap_or(source);
+    let line = before.matches('\n').count() + 1;
+    let column = before
+        .rsplit('\n')
+        .next()
+        .map_or(0, |line| line.chars().count())
+        + 1;
+    (line, column)
+}
 
-        paths
+/// Adds the closest expected name to serde's "unknown field" and "unknown variant" messages
+fn with_hint(message: &str) -> String {
+    let message = message.trim_end();
+    if !message.starts_with("unknown field") && !message.starts_with("unknown variant") {
+        return message.to_string();
     }
-
-    //TODO? this requires templates folder to live in the same directory as the binary, could add a config/cli flag
-    fn get_table(path: &Path) -> Table {
-        let template_str =
-            fs::read_to_string(path).expect(&format!("Error reading file: {}", path.display()));
-        let table = template_str.parse::<Table>().expect("Error parsing toml");
-        table
+    // serde quotes the unknown name first, followed by the expected ones
+    let mut names = message.split('`').skip(1).step_by(2);
+    let Some(unknown) = names.next() else {
+        return message.to_string();
+    };
+    let closest = names
+        .map(|name| (strsim::jaro_winkler(unknown, name), name))
+        .filter(|(similarity, _)| *similarity > 0.8)
+        .max_by(|a, b| a.0.total_cmp(&b.0));
+    match closest {
+        Some((_, name)) => format!("{}; did you mean `{}`?", message, name),
+        None => message.to_string(),
     }
 }
 
 #[cfg(test)]
-
 pub mod tests {
-    use toml::map::Map;
-
     use super::*;
 
     #[test]
     fn test_parse_toml() {
         let path = Path::new("test/__mocks__/_test.toml");
-        let template = TomlTemplate::new(path);
+        let template = TomlTemplate::new(path).expect("Error parsing template");
 
         dbg!(&template);
         let deps_table = template.get_dependencies();
@@ -244,29 +325,32 @@ pub mod tests {
     #[test]
     fn test_parse_deps() {
         let deps_table = get_deps_table();
-        let parsed_deps = TomlTemplate::fetch_deps(vec!["npm", "cargo"], &deps_table);
-        assert!(parsed_deps.contains_key("npm"));
-        assert!(parsed_deps.contains_key("cargo"));
+        assert!(deps_table.npm.is_some_and(|deps| !deps.is_empty()));
+        assert!(deps_table.cargo.is_some_and(|deps| !deps.is_empty()));
     }
 
     #[test]
     fn fetch_npm_deps() {
-        let deps_table = get_deps_table();
-        let parsed_deps = TomlTemplate::fetch_deps(vec!["npm"], &deps_table);
-        assert!(parsed_deps.contains_key("npm"));
-
-        let npm_deps = &parsed_deps["npm"].as_ref().unwrap();
+        let template = get_mock_template();
+        let npm_deps = template.get_dependencies()["npm"].as_ref().unwrap();
         assert!(npm_deps.iter().any(|dep| dep.name == "test_npm_dep_min"));
         assert!(npm_deps
             .iter()
             .any(|dep| dep.name == "test_npm_dev_dep_full"));
 
+        let min_dep = npm_deps
+            .iter()
+            .find(|dep| dep.name == "test_npm_dep_min")
+            .expect("Error finding dep");
+        assert_eq!(min_dep.version, "latest");
+        assert!(!min_dep.dev);
+
         let full_dev_dep = npm_deps
             .iter()
             .find(|dep| dep.name == "test_npm_dev_dep_full")
             .expect("Error finding dep");
         assert_eq!(full_dev_dep.version, "^1.0.0");
-        assert_eq!(full_dev_dep.dev, true);
+        assert!(full_dev_dep.dev);
 
         let then_cmds = full_dev_dep.then.as_ref().expect("Error getting then cmds");
         assert_eq!(then_cmds.len(), 2);
@@ -280,11 +364,8 @@ pub mod tests {
 
     #[test]
     fn fetch_cargo_deps() {
-        let deps_table = get_deps_table();
-        let parsed_deps = TomlTemplate::fetch_deps(vec!["cargo"], &deps_table);
-        assert!(parsed_deps.contains_key("cargo"));
-
-        let cargo_deps = &parsed_deps["cargo"].as_ref().unwrap();
+        let template = get_mock_template();
+        let cargo_deps = template.get_dependencies()["cargo"].as_ref().unwrap();
         assert!(cargo_deps
             .iter()
             .any(|dep| dep.name == "test_cargo_dep_min"));
@@ -297,7 +378,7 @@ pub mod tests {
             .find(|dep| dep.name == "test_cargo_dev_dep_full")
             .expect("Error finding dep");
         assert_eq!(full_dev_dep.version, "^1.0.0");
-        assert_eq!(full_dev_dep.dev, true);
+        assert!(full_dev_dep.dev);
 
         let then_cmds = full_dev_dep.then.as_ref().expect("Error getting then cmds");
         assert_eq!(then_cmds.len(), 2);
@@ -311,9 +392,8 @@ pub mod tests {
 
     #[test]
     fn extract_npm_scripts() {
-        let path = Path::new("test/__mocks__/_test.toml");
-        let table = TomlTemplate::get_table(path);
-        let parsed_scripts = TomlTemplate::parse_scripts(&table).expect("Error parsing deps");
+        let template = get_mock_template();
+        let parsed_scripts = template.get_scripts().expect("Error parsing scripts");
 
         assert!(parsed_scripts.contains_key("npm"));
         let npm_scripts = parsed_scripts
@@ -328,9 +408,8 @@ pub mod tests {
 
     #[test]
     fn extract_subfolders() {
-        let path = Path::new("test/__mocks__/_test.toml");
-        let table = TomlTemplate::get_table(path);
-        let folder_tree = TomlTemplate::parse_subfolders(&table);
+        let template = get_mock_template();
+        let folder_tree = template.get_subfolders();
 
         assert!(folder_tree.is_some());
         let folder_tree = folder_tree.unwrap();
@@ -348,14 +427,240 @@ pub mod tests {
         assert!(folder_tree.contains(&PathBuf::from("single_depth/double_depth/l2baz")));
     }
 
+    #[test]
+    fn reports_malformed_dep_key() {
+        let error = parse_error(
+            r#"title = "test"
+
+[[deps.cargo]]
+name = "serde"
+
+[[deps.cargo]]
+name = "tokio"
+dev = "true"
+"#,
+        );
+        assert_eq!(
+            error,
+            (
+                Some((8, 7)),
+                "deps.cargo[1].dev".to_string(),
+                "invalid type: string \"true\", expected a boolean".to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn suggests_misspelled_keys() {
+        let (location, key, message) = parse_error(
+            r#"title = "test"
+
+[[deps.cargo]]
+name = "clap"
+feature = ["derive"]
+"#,
+        );
+        assert_eq!(location, Some((5, 1)));
+        assert_eq!(key, "deps.cargo[0].feature");
+        assert!(message.ends_with("did you mean `features`?"), "{}", message);
+
+        let (_, key, message) = parse_error("title = \"test\"\ndescripton = \"typo\"\n");
+        assert_eq!(key, "descripton");
+        assert!(message.ends_with("did you mean `description`?"));
+    }
+
+    #[test]
+    fn checks_versions_per_ecosystem() {
+        let (location, key, message) = parse_error(
+            r#"title = "test"
+
+[[deps.npm]]
+name = "hono"
+version = "^4.0"
+
+[[deps.cargo]]
+name = "axum"
+version = "^0.7 || ^0.8"
+"#,
+        );
+        assert_eq!(location, Some((9, 11)));
+        assert_eq!(key, "deps.cargo[0].version");
+        assert!(
+            message.starts_with("`^0.7 || ^0.8` isn't a valid Cargo version requirement"),
+            "{}",
+            message
+        );
+
+        let template = parse(
+            r#"title = "test"
+
+[[deps.npm]]
+name = "hono"
+version = "^3.0"
+min_version = "3.11"
+
+[[deps.cargo]]
+name = "axum"
+version = ">=0.7.5, <0.9"
+min_version = "0.7"
+"#,
+        )
+        .unwrap();
+        assert_eq!(
+            template.get_warnings(),
+            ["stack_template.toml at `deps.npm[0].version`: `^3.0` allows versions of hono older than its min_version 3.11.0"]
+        );
+        let npm_deps = template.get_dependencies()["npm"].as_ref().unwrap();
+        assert_eq!(npm_deps[0].version, "^3.0");
+    }
+
+    #[test]
+    fn parses_stack_table() {
+        let template = parse(
+            r#"title = "test"
+
+[stack]
+languages = ["rust"]
+linters = ["clippy"]
+frontend_linters = ["stylelint"]
+template_engines = ["tera", "askama"]
+supports_frontend = true
+"#,
+        )
+        .unwrap();
+
+        let stack = template.get_stack();
+        assert_eq!(stack.languages, [Language::Rust]);
+        assert_eq!(stack.linters, ["clippy"]);
+        assert_eq!(stack.frontend_linters, ["stylelint"]);
+        assert_eq!(
+            stack.template_engines,
+            [TemplateEngine::Tera, TemplateEngine::Askama]
+        );
+        assert!(stack.supports_frontend && !stack.supports_containers && !stack.supports_db);
+
+        let (location, key, message) =
+            parse_error("title = \"test\"\n[stack]\nlanguages = [\"rust\", \"pyhton\"]\n");
+        assert_eq!(location, Some((3, 22)));
+        assert_eq!(key, "stack.languages[1]");
+        assert_eq!(
+            message,
+            "unknown variant `pyhton`, expected one of `rust`, `javascript`, `typescript`"
+        );
+    }
+
+    #[test]
+    fn reports_missing_title() {
+        let error = parse("description = \"untitled\"\n").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Invalid stack template stack_template.toml:1:1: missing field `title`"
+        );
+    }
+
+    #[test]
+    fn describes_template_in_schema() {
+        let schema = serde_json::to_value(schema()).unwrap();
+        let keys = |value: &serde_json::Value| {
+            let mut keys = value["properties"]
+                .as_object()
+                .unwrap()
+                .keys()
+                .cloned()
+                .collect::<Vec<_>>();
+            keys.sort();
+            keys
+        };
+
+        assert_eq!(schema["title"], STACK_TEMPLATE);
+        assert_eq!(schema["required"], serde_json::json!(["title"]));
+        assert_eq!(
+            keys(&schema),
+            [
+                "deps",
+                "description",
+                "scripts",
+                "stack",
+                "subfolders",
+                "title"
+            ]
+        );
+        assert_eq!(schema["additionalProperties"], false);
+
+        let npm = &schema["definitions"]["NpmDependency"];
+        assert_eq!(
+            keys(npm),
+            [
+                "dev",
+                "features",
+                "min_version",
+                "name",
+                "then",
+                "version",
+                "when"
+            ]
+        );
+        let cargo = &schema["definitions"]["CargoDependency"];
+        assert_eq!(
+            keys(cargo),
+            [
+                "branch",
+                "build",
+                "default-features",
+                "dev",
+                "features",
+                "git",
+                "min_version",
+                "name",
+                "optional",
+                "package",
+                "path",
+                "registry",
+                "rename",
+                "rev",
+                "tag",
+                "target",
+                "then",
+                "version",
+                "when"
+            ]
+        );
+        for dependency in [npm, cargo] {
+            assert_eq!(dependency["additionalProperties"], false);
+            assert_eq!(dependency["properties"]["version"]["default"], "latest");
+        }
+        assert_eq!(
+            schema["definitions"]["Folders"]["additionalProperties"]["$ref"],
+            "#/definitions/Folders"
+        );
+    }
+
     // Helpers
-    fn get_deps_table() -> Map<String, Value> {
+    fn get_mock_template() -> TomlTemplate {
+        TomlTemplate::new(Path::new("test/__mocks__/_test.toml")).expect("Error parsing template")
+    }
+
+    fn get_deps_table() -> DepsTable {
         let path = Path::new("test/__mocks__/_test.toml");
-        let toml_table = TomlTemplate::get_table(path);
-        let deps_table = match toml_table.get("deps") {
-            Some(deps) => deps.as_table().expect("Error parsing dependencies"),
-            None => panic!("No deps keys found in TOML template file"),
-        };
-        deps_table.to_owned()
+        let source = read_file(path, "stack template").expect("Error reading template");
+        deserialize_template::<TemplateFile>(path, &source)
+            .expect("Error parsing template")
+            .deps
+    }
+
+    fn parse(source: &str) -> Result<TomlTemplate> {
+        TomlTemplate::parse(Path::new("stack_template.toml"), source)
+    }
+
+    fn parse_error(source: &str) -> (Option<(usize, usize)>, String, String) {
+        match parse(source).unwrap_err() {
+            GerminateError::Template {
+                location,
+                key,
+                message,
+                ..
+            } => (location, key, message),
+            error => panic!("expected a template error, got {}", error),
+        }
     }
 }
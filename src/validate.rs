@@ -0,0 +1,234 @@
+use std::{
+    env,
+    ffi::OsStr,
+    fs,
+    path::{Component, Path},
+};
+
+use handlebars::Template;
+
+use crate::{
+    error::{GerminateError, Result},
+    file_system,
+    module::Module,
+    stack::STACK_TEMPLATE,
+    template_generator,
+    toml_parser::TomlTemplate,
+};
+
+/// Folders of a stack whose files are rendered with handlebars, and whether only their `.hbs`
+/// files are. `before_install` and `template_engine` files are copied as is, so they aren't checked
+const TEMPLATE_DIRS: [(&str, bool); 2] = [("docker", false), ("after_install", true)];
+
+/// What `germinate validate` found in a stack
+#[derive(Debug, Default)]
+pub struct Findings {
+    /// Mistakes that would break a build
+    pub problems: Vec<String>,
+    /// Things worth a look that don't stop the stack from being built
+    pub warnings: Vec<String>,
+}
+
+impl Findings {
+    pub fn into_result(self, dir: &Path) -> Result<()> {
+        match self.problems.is_empty() {
+            true => Ok(()),
+            false => Err(GerminateError::Validation {
+                dir: dir.to_path_buf(),
+                problems: self.problems,
+            }),
+        }
+    }
+}
+
+/// Checks the stack in `dir` without building anything, collecting every problem found
+pub fn check(dir: &Path) -> Findings {
+    let search_path = env::var_os("PATH").unwrap_or_default();
+    check_with(dir, &search_path)
+}
+
+/// Like `check`, looking up `then` programs in `search_path`
+fn check_with(dir: &Path, search_path: &OsStr) -> Findings {
+    let mut findings = Findings::default();
+    let problems = &mut findings.problems;
+
+    // the remaining template checks need a parsed template, the file checks don't
+    match TomlTemplate::new(&dir.join(STACK_TEMPLATE)) {
+        Ok(template) => {
+            check_then_programs(&template, search_path, problems);
+            check_subfolders(&template, problems);
+            findings.warnings = template.get_warnings().to_vec();
+        }
+        Err(e) => problems.push(e.to_string()),
+    }
+    for (name, hbs_only) in TEMPLATE_DIRS {
+        check_templates(&dir.join(name), hbs_only, &mut findings.problems);
+    }
+
+    findings
+}
+
+fn check_then_programs(template: &TomlTemplate, search_path: &OsStr, problems: &mut Vec<String>) {
+    for manager in ["npm", "cargo"] {
+        let Some(Some(modules)) = template.get_dependencies().get(manager) else {
+            continue;
+        };
+        for (index, Module { then, .. }) in modules.iter().enumerate() {
+            let commands = then.iter().flatten().enumerate();
+            for (command_index, command) in commands {
+                let program = &command[0];
+                if !is_on_path(program, search_path) {
+                    problems.push(format!(
+                        "`deps.{}[{}].then[{}]` runs `{}`, which isn't on PATH",
+                        manager, index, command_index, program
+                    ));
+                }
+            }
+        }
+    }
+}
+
+// programs given as a path are run from the project folder, which doesn't exist yet
+fn is_on_path(program: &str, search_path: &OsStr) -> bool {
+    if Path::new(program).components().count() > 1 {
+        return true;
+    }
+    let file_name = format!("{}{}", program, env::consts::EXE_SUFFIX);
+    env::split_paths(search_path).any(|dir| dir.join(&file_name).is_file())
+}
+
+fn check_subfolders(template: &TomlTemplate, problems: &mut Vec<String>) {
+    for path in template.get_subfolders().into_iter().flatten() {
+        let escapes = path.components().any(|component| {
+            matches!(
+                component,
+                Component::ParentDir | Component::RootDir | Component::Prefix(_)
+            )
+        });
+        if escapes {
+            problems.push(format!(
+                "subfolder `{}` must be a relative path inside the project folder",
+                path.display()
+            ));
+        }
+    }
+}
+
+fn check_templates(dir: &Path, hbs_only: bool, problems: &mut Vec<String>) {
+    if !dir.is_dir() {
+        return;
+    }
+    let files = match file_system::list_files(dir) {
+        Ok(files) => files,
+        Err(e) => {
+            problems.push(format!("Failed to read {}: {}", dir.display(), e));
+            return;
+        }
+    };
+    for file in files {
+        if hbs_only && !template_generator::is_template(&file) {
+            continue;
+        }
+        let path = dir.join(file);
+        let source = match fs::read_to_string(&path) {
+            Ok(source) => source,
+            Err(e) => {
+                problems.push(format!("Failed to read {}: {}", path.display(), e));
+                continue;
+            }
+        };
+        if let Err(e) = Template::compile(&source) {
+            let location = match (e.line_no, e.column_no) {
+                (Some(line), Some(column)) => format!(":{}:{}", line, column),
+                _ => String::new(),
+            };
+            problems.push(format!("{}{}: {}", path.display(), location, e.reason()));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ffi::OsString;
+
+    use tempfile::TempDir;
+
+    use super::*;
+
+    #[test]
+    fn accepts_stock_stacks() {
+        for stack in ["rsapi", "rscli", "tsapi", "tscli"] {
+            let dir = Path::new("templates").join(stack);
+            // `then` programs depend on the machine, so only the other checks are expected to pass
+            let found = check_with(&dir, &OsString::new()).problems;
+            assert!(
+                found
+                    .iter()
+                    .all(|problem| problem.ends_with("isn't on PATH")),
+                "{}: {:?}",
+                stack,
+                found
+            );
+        }
+    }
+
+    #[test]
+    fn reports_every_problem() {
+        let stack = TempDir::new().unwrap();
+        let bin = TempDir::new().unwrap();
+        fs::write(bin.path().join("installed"), "").unwrap();
+        fs::write(
+            stack.path().join(STACK_TEMPLATE),
+            r#"title = "broken"
+
+[subfolders]
+src = {}
+"../outside" = {}
+
+[[deps.cargo]]
+name = "diesel_cli"
+then = [["installed", "setup"], ["missing", "--yes"]]
+"#,
+        )
+        .unwrap();
+        fs::create_dir_all(stack.path().join("docker")).unwrap();
+        fs::write(
+            stack.path().join("docker/Dockerfile"),
+            "FROM {{#if x}}rust\n",
+        )
+        .unwrap();
+        fs::create_dir_all(stack.path().join("after_install")).unwrap();
+        fs::write(stack.path().join("after_install/main.rs"), "{{#if x}}").unwrap();
+        fs::write(stack.path().join("after_install/lib.rs.hbs"), "{{#if x}}").unwrap();
+
+        let found = check_with(stack.path(), bin.path().as_os_str()).problems;
+        assert_eq!(found.len(), 4, "{:?}", found);
+        assert_eq!(
+            found[0],
+            "`deps.cargo[0].then[1]` runs `missing`, which isn't on PATH"
+        );
+        assert_eq!(
+            found[1],
+            "subfolder `../outside` must be a relative path inside the project folder"
+        );
+        assert!(found[2].contains("docker/Dockerfile"), "{}", found[2]);
+        assert!(
+            found[3].contains("after_install/lib.rs.hbs"),
+            "{}",
+            found[3]
+        );
+
+        fs::write(
+            stack.path().join(STACK_TEMPLATE),
+            "title = \"broken\"\ndev = true\n",
+        )
+        .unwrap();
+        let found = check_with(stack.path(), bin.path().as_os_str()).problems;
+        assert_eq!(found.len(), 3, "{:?}", found);
+        assert!(
+            found[0].contains(":2:1 at `dev`: unknown field `dev`"),
+            "{}",
+            found[0]
+        );
+    }
+}
@@ -0,0 +1,406 @@
+use std::marker::PhantomData;
+
+use schemars::{
+    gen::SchemaGenerator,
+    schema::{InstanceType, Metadata, Schema, SchemaObject},
+    JsonSchema,
+};
+use semver::{BuildMetadata, Comparator, Op, Version, VersionReq};
+use serde::{de::Error, Deserialize, Deserializer};
+
+/// How an ecosystem writes version requirements
+pub trait Syntax {
+    /// Names the ecosystem in messages
+    const NAME: &'static str;
+    const DESCRIPTION: &'static str;
+
+    /// The lowest version the requirement allows, if that's known before installing,
+    /// or why it isn't a valid requirement
+    fn lowest(requirement: &str) -> Result<Option<Version>, String>;
+}
+
+/// Requirements as `cargo add name@requirement` takes them, e.g. `1.2` or `>=1.2, <2`
+#[derive(Debug, Clone, Copy)]
+pub struct Cargo;
+
+/// Ranges as npm and the other JavaScript package managers take them, e.g. `^1.2.0` or `1.x || >=3`
+#[derive(Debug, Clone, Copy)]
+pub struct Npm;
+
+/// A dependency's `version`, checked against its ecosystem's syntax when the template is loaded
+#[derive(Debug, Clone)]
+pub struct Requirement<S> {
+    pub text: String,
+    // None for `latest`, which always installs the newest release, and for specs that don't
+    // name a version, like npm dist-tags
+    lowest: Option<Version>,
+    syntax: PhantomData<S>,
+}
+
+impl<S> Requirement<S> {
+    /// Whether the requirement lets the package manager pick a version older than `minimum`
+    pub fn allows_below(&self, minimum: &Version) -> bool {
+        self.lowest.as_ref().is_some_and(|lowest| lowest < minimum)
+    }
+}
+
+impl<'de, S: Syntax> Deserialize<'de> for Requirement<S> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        let lowest = match text.as_str() {
+            "latest" => None,
+            requirement => S::lowest(requirement).map_err(|reason| {
+                D::Error::custom(format!(
+                    "`{}` isn't a valid {} version requirement: {}",
+                    requirement,
+                    S::NAME,
+                    reason
+                ))
+            })?,
+        };
+        Ok(Self {
+            text,
+            lowest,
+            syntax: PhantomData,
+        })
+    }
+}
+
+impl<S: Syntax> JsonSchema for Requirement<S> {
+    // inlined, so the name only shows up in the dependency tables' names
+    fn is_referenceable() -> bool {
+        false
+    }
+
+    // the marker's own name, `Cargo` or `Npm`
+    fn schema_name() -> String {
+        let name = std::any::type_name::<S>();
+        name.rsplit("::").next().unwrap_or(name).to_string()
+    }
+
+    fn json_schema(_: &mut SchemaGenerator) -> Schema {
+        SchemaObject {
+            instance_type: Some(InstanceType::String.into()),
+            metadata: Some(Box::new(Metadata {
+                description: Some(S::DESCRIPTION.to_string()),
+                default: Some("latest".into()),
+                ..Default::default()
+            })),
+            ..Default::default()
+        }
+        .into()
+    }
+}
+
+impl Syntax for Cargo {
+    const NAME: &'static str = "Cargo";
+    const DESCRIPTION: &'static str =
+        "Cargo version requirement to install, e.g. `1.2` or `>=1.2, <2`, or `latest`";
+
+    fn lowest(requirement: &str) -> Result<Option<Version>, String> {
+        let requirement = VersionReq::parse(requirement).map_err(|e| e.to_string())?;
+        // every comparator has to match, so the highest lower bound wins
+        Ok(Some(
+            requirement
+                .comparators
+                .iter()
+                .filter_map(comparator_lowest)
+                .max()
+                .unwrap_or(Version::new(0, 0, 0)),
+        ))
+    }
+}
+
+fn comparator_lowest(comparator: &Comparator) -> Option<Version> {
+    let version = Version {
+        major: comparator.major,
+        minor: comparator.minor.unwrap_or(0),
+        patch: comparator.patch.unwrap_or(0),
+        pre: comparator.pre.clone(),
+        build: BuildMetadata::EMPTY,
+    };
+    match comparator.op {
+        Op::Less | Op::LessEq => None,
+        Op::Greater => {
+            let parts =
+                1 + comparator.minor.is_some() as usize + comparator.patch.is_some() as usize;
+            Some(next_version(version, parts))
+        }
+        _ => Some(version),
+    }
+}
+
+/// The lowest version above `version`, written with its first `parts` numbers: `>1.4.0` starts at
+/// 1.4.1 and `>1.4` at 1.5.0. Above a prerelease the next one isn't known, so its own version is
+/// kept, which only misses a `min_version` that's the prerelease itself
+fn next_version(version: Version, parts: usize) -> Version {
+    if !version.pre.is_empty() {
+        return version;
+    }
+    match parts {
+        0 => version,
+        1 => Version::new(version.major + 1, 0, 0),
+        2 => Version::new(version.major, version.minor + 1, 0),
+        _ => Version::new(version.major, version.minor, version.patch + 1),
+    }
+}
+
+impl Syntax for Npm {
+    const NAME: &'static str = "npm";
+    const DESCRIPTION: &'static str =
+        "npm version range to install, e.g. `^1.2.0` or `1.x || >=3`, `latest` or another dist-tag, \
+         or a spec such as `workspace:*`, `npm:pkg@^1`, `file:../pkg` or a git or tarball url";
+
+    // node-semver's grammar: `||` separated ranges of space separated comparators, or a hyphen range
+    fn lowest(requirement: &str) -> Result<Option<Version>, String> {
+        // protocols (`workspace:`, `npm:`, `file:`, `git+https:`...) and `user/repo` specs aren't
+        // ranges, and what they install is only known once it's fetched
+        if NPM_PROTOCOLS
+            .iter()
+            .any(|protocol| requirement.starts_with(protocol))
+            || is_github_repo(requirement)
+        {
+            return Ok(None);
+        }
+        let lowest = requirement
+            .split("||")
+            .map(range_lowest)
+            .collect::<Result<Vec<_>, _>>()
+            .map(|lowest| lowest.into_iter().min().unwrap_or(Version::new(0, 0, 0)));
+        match lowest {
+            Ok(lowest) => Ok(Some(lowest)),
+            // npm refuses tags that read as ranges, so anything else shaped like a name is a tag
+            Err(_) if is_dist_tag(requirement) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+const NPM_PROTOCOLS: [&str; 8] = [
+    "workspace:",
+    "npm:",
+    "file:",
+    "link:",
+    "git+",
+    "github:",
+    "http:",
+    "https:",
+];
+
+// `user/repo`, optionally with a `#commit-ish`. GitHub user names are letters, digits and `-`
+fn is_github_repo(requirement: &str) -> bool {
+    let repo = requirement.split('#').next().unwrap_or_default();
+    let is_name = |name: &str, extra: &[char]| {
+        !name.is_empty()
+            && name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || extra.contains(&c))
+    };
+    matches!(repo.split_once('/'), Some((owner, name)) if is_name(owner, &[]) && is_name(name, &['_', '.']))
+}
+
+// `v1.2.3.4` is a mistyped version rather than a tag
+fn is_dist_tag(requirement: &str) -> bool {
+    let mut chars = requirement.chars();
+    let looks_like_version = matches!(
+        (chars.next(), chars.next()),
+        (Some('v' | 'V'), Some('0'..='9'))
+    );
+    requirement.starts_with(|c: char| c.is_ascii_alphabetic())
+        && !looks_like_version
+        && requirement
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+}
+
+fn range_lowest(range: &str) -> Result<Version, String> {
+    let range = range.trim();
+    if let Some((from, to)) = range.split_once(" - ") {
+        partial_version(to.trim())?;
+        return partial_version(from.trim());
+    }
+
+    let mut lowest = Version::new(0, 0, 0);
+    let mut tokens = range.split_whitespace();
+    while let Some(token) = tokens.next() {
+        let split = token
+            .find(|c| !matches!(c, '<' | '>' | '=' | '~' | '^'))
+            .unwrap_or(token.len());
+        let (op, version) = token.split_at(split);
+        // node-semver allows a space between the operator and its version
+        let version = match version {
+            "" => tokens
+                .next()
+                .ok_or_else(|| format!("`{}` is missing a version", op))?,
+            version => version,
+        };
+        let parts = version_parts(version);
+        let version = partial_version(version)?;
+        match op {
+            "<" | "<=" => {}
+            ">" => lowest = lowest.max(next_version(version, parts)),
+            "" | "=" | ">=" | "~" | "~>" | "^" => lowest = lowest.max(version),
+            op => return Err(format!("unknown operator `{}`", op)),
+        }
+    }
+    Ok(lowest)
+}
+
+/// How many numbers a partial version gives before any wildcard, e.g. 2 for `1.2` and `1.2.x`
+fn version_parts(version: &str) -> usize {
+    let core = version.split(['-', '+']).next().unwrap_or_default();
+    core.split('.')
+        .take_while(|part| !matches!(*part, "x" | "X" | "*"))
+        .count()
+}
+
+/// Reads `1`, `1.2`, `1.x` or `1.2.3-beta.1`, filling missing and wildcard parts with 0
+fn partial_version(version: &str) -> Result<Version, String> {
+    let version = version.strip_prefix(['v', '=']).unwrap_or(version);
+    let (core, qualifier) = version.split_at(version.find(['-', '+']).unwrap_or(version.len()));
+    let parts = core.split('.').collect::<Vec<_>>();
+    if parts.len() > 3 {
+        return Err(format!("`{}` has more than three parts", version));
+    }
+
+    let mut numbers = [0; 3];
+    let mut wildcard = false;
+    for (index, part) in parts.iter().enumerate() {
+        match *part {
+            "x" | "X" | "*" => wildcard = true,
+            part if !wildcard && !part.is_empty() && part.chars().all(|c| c.is_ascii_digit()) => {
+                numbers[index] = part
+                    .parse()
+                    .map_err(|_| format!("`{}` is too large", part))?;
+            }
+            part => return Err(format!("`{}` isn't a version number", part)),
+        }
+    }
+    match qualifier {
+        "" => Ok(Version::new(numbers[0], numbers[1], numbers[2])),
+        _ if parts.len() == 3 && !wildcard => Version::parse(version).map_err(|e| e.to_string()),
+        _ => Err(format!(
+            "`{}` can only have a prerelease or build with all three numbers",
+            version
+        )),
+    }
+}
+
+/// Reads a dependency's `min_version`
+pub fn minimum<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Version>, D::Error> {
+    let text = String::deserialize(deserializer)?;
+    let core = text.split(['-', '+']).next().unwrap_or_default();
+    match core.contains(['x', 'X', '*']) {
+        true => Err(D::Error::custom(format!(
+            "`{}` isn't a version, wildcards are only allowed in `version`",
+            text
+        ))),
+        false => partial_version(&text).map(Some).map_err(D::Error::custom),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lowest<S: Syntax>(requirement: &str) -> String {
+        S::lowest(requirement).unwrap().unwrap().to_string()
+    }
+
+    #[test]
+    fn reads_cargo_requirements() {
+        assert_eq!(lowest::<Cargo>("1.2"), "1.2.0");
+        assert_eq!(lowest::<Cargo>("^0.8.3"), "0.8.3");
+        assert_eq!(lowest::<Cargo>(">=1.2, <2"), "1.2.0");
+        assert_eq!(lowest::<Cargo>("<2"), "0.0.0");
+        assert_eq!(lowest::<Cargo>("=1.0.0-rc.1"), "1.0.0-rc.1");
+        assert_eq!(lowest::<Cargo>("*"), "0.0.0");
+        assert_eq!(lowest::<Cargo>(">1.4.0"), "1.4.1");
+        assert_eq!(lowest::<Cargo>(">1.4, <2"), "1.5.0");
+
+        for invalid in ["garbage", "^1.2 || ^2", "1.2.3 - 2", ">=1.2 <2"] {
+            assert!(Cargo::lowest(invalid).is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn reads_npm_ranges() {
+        assert_eq!(lowest::<Npm>("^1.2.3"), "1.2.3");
+        assert_eq!(lowest::<Npm>("~1.2"), "1.2.0");
+        assert_eq!(lowest::<Npm>("1.x"), "1.0.0");
+        assert_eq!(lowest::<Npm>(">= 1.2.7 <1.3.0"), "1.2.7");
+        assert_eq!(lowest::<Npm>("1.2.3 - 2.3.4"), "1.2.3");
+        assert_eq!(lowest::<Npm>("^3.1 || ^2"), "2.0.0");
+        assert_eq!(lowest::<Npm>("v2.0.0-beta.1"), "2.0.0-beta.1");
+        assert_eq!(lowest::<Npm>("*"), "0.0.0");
+        assert_eq!(lowest::<Npm>(""), "0.0.0");
+        assert_eq!(lowest::<Npm>(">1.4.0"), "1.4.1");
+        assert_eq!(lowest::<Npm>("> 1.4 <2"), "1.5.0");
+        assert_eq!(lowest::<Npm>(">1.x"), "2.0.0");
+
+        // specs that don't name a version install whatever they point at, like `latest`
+        for spec in [
+            "next",
+            "beta",
+            "canary-2024.1",
+            "workspace:*",
+            "workspace:^",
+            "npm:react@^18",
+            "file:../shared",
+            "link:../shared",
+            "git+https://github.com/user/repo.git#v1.2.0",
+            "github:user/repo",
+            "user/repo",
+            "user/repo#v1.2.0",
+            "https://example.com/pkg-1.0.0.tgz",
+        ] {
+            assert_eq!(Npm::lowest(spec), Ok(None), "{}", spec);
+        }
+
+        for invalid in [
+            "^1.2.3.4",
+            ">>1",
+            "1.2-beta",
+            "1.x.3",
+            "^",
+            "v1.2.3.4",
+            "next tag",
+            "^1.2/3",
+            ">=1:2",
+            "../shared",
+            "foo:bar",
+        ] {
+            assert!(Npm::lowest(invalid).is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn compares_against_minimum() {
+        let requirement = |text: &str| {
+            Requirement::<Npm>::deserialize(toml::Value::String(text.to_string())).unwrap()
+        };
+        let minimum = Version::new(1, 4, 0);
+        assert!(requirement("^1.0").allows_below(&minimum));
+        assert!(!requirement("^1.4").allows_below(&minimum));
+        assert!(!requirement(">1.3").allows_below(&minimum));
+        assert!(requirement(">1.3.9").allows_below(&minimum));
+        assert!(!requirement("latest").allows_below(&minimum));
+        assert!(!requirement("next").allows_below(&minimum));
+
+        let min_version = |text: &str| super::minimum(toml::Value::String(text.to_string()));
+        assert_eq!(
+            min_version("1.0.0-next").unwrap().unwrap().to_string(),
+            "1.0.0-next"
+        );
+        assert!(min_version("1.x").is_err());
+
+        let error = Requirement::<Cargo>::deserialize(toml::Value::String("~>1".to_string()))
+            .unwrap_err()
+            .to_string();
+        assert!(
+            error.starts_with("`~>1` isn't a valid Cargo version requirement"),
+            "{}",
+            error
+        );
+    }
+}
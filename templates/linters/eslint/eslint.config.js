@@ -0,0 +1,11 @@
+// @ts-check
+import eslint from "@eslint/js";
+import tseslint from "typescript-eslint";
+
+export default tseslint.config(
+  eslint.configs.recommended,
+  ...tseslint.configs.recommended,
+  {
+    ignores: ["node_modules", "dist"{{#if spa}}, "frontend"{{/if}}],
+  },
+);
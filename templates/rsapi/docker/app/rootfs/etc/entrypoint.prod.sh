@@ -1,2 +1,4 @@
 #!/bin/sh
-./app/{{ app_name }}
+# the server looks for the built SPA relative to /app
+cd /app
+./{{ app_name }}
@@ -0,0 +1,25 @@
+use askama::Template;
+use axum::{http::StatusCode, response::Html, routing::get, Router};
+
+/// Routes for the pages rendered from `views/`, which askama compiles into the binary
+pub fn router() -> Router {
+    Router::new().route("/welcome", get(welcome))
+}
+
+// paths are relative to views/, see askama.toml
+#[derive(Template)]
+#[template(path = "welcome.html")]
+struct Welcome {
+    title: &'static str,
+    message: &'static str,
+}
+
+async fn welcome() -> Result<Html<String>, StatusCode> {
+    let page = Welcome {
+        title: env!("CARGO_PKG_NAME"),
+        message: "This page was rendered with Askama.",
+    };
+    page.render()
+        .map(Html)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)
+}
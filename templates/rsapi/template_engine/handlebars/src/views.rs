@@ -0,0 +1,34 @@
+use std::sync::Arc;
+
+use axum::{extract::State, http::StatusCode, response::Html, routing::get, Router};
+use handlebars::{DirectorySourceOptions, Handlebars};
+use serde_json::json;
+
+type Views = Arc<Handlebars<'static>>;
+
+/// Routes for the pages rendered from `views/`, registered once at startup
+pub fn router() -> Router {
+    Router::new()
+        .route("/welcome", get(welcome))
+        .with_state(load())
+}
+
+// every .hbs file under views/ is registered by its path, e.g. `layouts/main` or `partials/header`
+fn load() -> Views {
+    let mut views = Handlebars::new();
+    views
+        .register_templates_directory("views", DirectorySourceOptions::default())
+        .expect("Failed to load views");
+    Arc::new(views)
+}
+
+async fn welcome(State(views): State<Views>) -> Result<Html<String>, StatusCode> {
+    let data = json!({
+        "title": env!("CARGO_PKG_NAME"),
+        "message": "This page was rendered with Handlebars.",
+    });
+    views
+        .render("welcome", &data)
+        .map(Html)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)
+}
@@ -0,0 +1,33 @@
+use std::sync::Arc;
+
+use axum::{extract::State, http::StatusCode, response::Html, routing::get, Router};
+use minijinja::{context, path_loader, Environment};
+
+type Views = Arc<Environment<'static>>;
+
+/// Routes for the pages rendered from `views/`, with the environment set up once at startup
+pub fn router() -> Router {
+    Router::new()
+        .route("/welcome", get(welcome))
+        .with_state(load())
+}
+
+// views are loaded from views/ on first use and named by their path, e.g. `layouts/main.html`
+fn load() -> Views {
+    let mut views = Environment::new();
+    views.set_loader(path_loader("views"));
+    Arc::new(views)
+}
+
+async fn welcome(State(views): State<Views>) -> Result<Html<String>, StatusCode> {
+    views
+        .get_template("welcome.html")
+        .and_then(|view| {
+            view.render(context! {
+                title => env!("CARGO_PKG_NAME"),
+                message => "This page was rendered with MiniJinja.",
+            })
+        })
+        .map(Html)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)
+}
@@ -0,0 +1,28 @@
+use std::sync::Arc;
+
+use axum::{extract::State, http::StatusCode, response::Html, routing::get, Router};
+use tera::{Context, Tera};
+
+type Views = Arc<Tera>;
+
+/// Routes for the pages rendered from `views/`, loaded once at startup
+pub fn router() -> Router {
+    Router::new()
+        .route("/welcome", get(welcome))
+        .with_state(load())
+}
+
+// every .html file under views/ is named by its path, e.g. `layouts/main.html`
+fn load() -> Views {
+    Arc::new(Tera::new("views/**/*.html").expect("Failed to load views"))
+}
+
+async fn welcome(State(views): State<Views>) -> Result<Html<String>, StatusCode> {
+    let mut context = Context::new();
+    context.insert("title", env!("CARGO_PKG_NAME"));
+    context.insert("message", "This page was rendered with Tera.");
+    views
+        .render("welcome.html", &context)
+        .map(Html)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)
+}
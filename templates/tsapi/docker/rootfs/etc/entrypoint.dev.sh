@@ -1,2 +1,2 @@
 #!/bin/sh
-bun run dev
+{{ package_manager }} run dev
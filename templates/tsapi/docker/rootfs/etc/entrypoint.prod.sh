@@ -1,2 +1,2 @@
 #!/bin/sh
-bun start
+{{ package_manager }} run start
@@ -0,0 +1,25 @@
+import Handlebars from "handlebars";
+import { readdirSync, readFileSync } from "node:fs";
+import { join } from "node:path";
+
+const VIEWS_DIR = "views";
+
+// every .hbs file under views/ is registered by its path, e.g. "layouts/main" or "partials/header"
+const views = Handlebars.create();
+const templates = new Map<string, HandlebarsTemplateDelegate>();
+
+for (const file of readdirSync(VIEWS_DIR, { recursive: true, encoding: "utf8" })) {
+  if (!file.endsWith(".hbs")) continue;
+  const name = file.slice(0, -".hbs".length).replaceAll("\\", "/");
+  const source = readFileSync(join(VIEWS_DIR, file), "utf8");
+  views.registerPartial(name, source);
+  templates.set(name, views.compile(source));
+}
+
+export function render(view: string, data: object): string {
+  const template = templates.get(view);
+  if (!template) {
+    throw new Error(`No view named ${view} in ${VIEWS_DIR}/`);
+  }
+  return template(data);
+}